@@ -1,459 +1,781 @@
 // Starter code for the Rust Task Manager challenge
+// The REPL and command-line front end; tasks, storage and parsing live in task_manager/
 
-use std::fs::{File, OpenOptions};
-use std::io::{self, Write, Read};
-use std::path::Path;
-use std::fmt;
-use std::collections::HashMap;
-
-// Task status enum
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum TaskStatus {
-    Pending,
-    Completed,
+mod task_manager;
+
+use std::error::Error;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::process::ExitCode;
+use std::collections::HashSet;
+use std::ops::Range;
+use chrono::{DateTime, Duration, Local, NaiveDate};
+
+use task_manager::*;
+
+fn display_help() {
+    println!("Task Manager - Available Commands:");
+    println!("----------------------------------");
+    println!("add <title> <description> [due_date] - Add a new task");
+    println!("    due_date: YYYY-MM-DD, today, tomorrow, +Nd or +Nw");
+    println!("    also: add --title=<title> --description=<text> --due=<date>");
+    println!("    options: --priority=low|medium|high|urgent --tags=work,home");
+    println!("    --repeat=daily|weekly|weekly:mon,thu|monthly:15|every:3d - Repeat the task;");
+    println!("    completing it adds the next occurrence");
+    println!("    --parent=<id> makes it a subtask; --depends=1,2 blocks completing it until those are done");
+    println!("list [query] [--sort=due|priority|id|title] [--desc] [--format=text|json] - List tasks as a table");
+    println!("    query terms (all must match): status:pending tag:work priority>=high due<2026-11-01");
+    println!("    operators: : != < <= > >=, and due:none for tasks without a due date");
+    println!("    --upcoming=14d lists due dates over the next 14 days, repeating tasks included");
+    println!("show <id> - Show all details of a task");
+    println!("tree [id] - Show tasks with their subtasks and what they are waiting on");
+    println!("search <words> - Find tasks by title and description, best matches first");
+    println!("    words match ignoring case, as prefixes (\"mil\" finds \"milk\") or with small typos");
+    println!("pending - List pending tasks");
+    println!("completed - List completed tasks");
+    println!("overdue - List pending tasks past their due date");
+    println!("due-today - List tasks due today");
+    println!("due-before <date> - List tasks due before a date");
+    println!("complete <id> - Mark a task as completed, stopping its timer");
+    println!("start <id> [--parallel] - Start timing work on a task");
+    println!("    only one timer runs at a time; --parallel keeps the others running");
+    println!("stop <id> - Stop a task's timer; 'stats' shows the time tracked per task and per day");
+    println!("edit <id> [--title=..] [--description=..] [--due=<date>|none] [--status=..] - Change a task");
+    println!("    also --priority=.., --tags=.. (replaces the tags), --repeat=<rule>|none,");
+    println!("    --parent=<id>|none and --depends=<ids>|none");
+    println!("delete <id> - Delete a task");
+    println!("move <id> <project> - Move a task to another project");
+    println!("project [list] - List projects; other commands work on the current project");
+    println!("project create|switch <name> - Create a project, or make it the current one");
+    println!("project rename <name> <new_name> - Rename a project");
+    println!("project delete <name> [--force] - Delete a project; --force also deletes its tasks");
+    println!("undo - Revert the last add, edit, complete, start, stop, snooze, delete, import or move");
+    println!("redo - Re-apply the last undone change");
+    println!("save <filename> [--format=json|pipe|sqlite] - Save tasks to a file (JSON for .json, SQLite for .db, .sqlite or .sqlite3 files)");
+    println!("load <filename> [--strict] - Load tasks from a JSON, legacy or SQLite file");
+    println!("    tasks that cannot be loaded are skipped and listed; --strict refuses the file instead");
+    println!("    while autosave is on, it moves to the loaded file");
+    println!("migrate <filename> [new_filename] [--strict] - Convert a legacy file to JSON, or to SQLite if new_filename ends in .db, .sqlite or .sqlite3");
+    println!("export <filename> [--format=csv|md|ics] - Export tasks as CSV, a Markdown checklist or iCalendar");
+    println!("import <filename> [--format=csv|md|ics] [--map=<header>=<field>,..] - Add tasks from such a file");
+    println!("    --map assigns CSV columns to title, description, due, status, priority, tags or ignore");
+    println!("autosave [filename|off] - Save after every change (default file: {})", DEFAULT_TASK_FILE);
+    println!("reminders [on|off] [--lead=<time>] - List due and overdue tasks, or turn reminders on or off");
+    println!("    the REPL shows them at startup and before prompts; --lead=3d reminds 3 days ahead (default 1d)");
+    println!("snooze <id> [<time>|none] - Hold back reminders about a task for a while, e.g. 2h (default 1d)");
+    println!("stats - Show task statistics");
+    println!("help - Display this help message");
+    println!("quit - Exit the program");
+    println!();
+    println!("Quote arguments that contain spaces: add \"Buy milk\" 'Two liters of oat milk' tomorrow");
+    println!("Flags take their value as --sort=due or --sort due");
+    println!();
+    println!("Commands can also be run without the REPL, loading and saving a file:");
+    println!("  module5_final add --title \"Buy milk\" --description \"Oat milk\" --file tasks.json");
+    println!("  module5_final list --status pending --format json --file tasks.json");
+    println!("Add --strict to refuse a --file containing lines that cannot be loaded.");
+    println!("With only --file, the REPL starts on that file and saves to it after every change.");
+    println!("A --file ending in .db, .sqlite or .sqlite3 is an SQLite database, which other");
+    println!("processes can safely use at the same time.");
+    println!();
 }
 
-impl fmt::Display for TaskStatus {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            TaskStatus::Pending => write!(f, "Pending"),
-            TaskStatus::Completed => write!(f, "Completed"),
-        }
+// Widest each table column may get before its contents are truncated
+const MAX_TITLE_WIDTH: usize = 32;
+
+const MAX_TAGS_WIDTH: usize = 24;
+
+// Shorten text to at most `width` characters, marking the cut with an ellipsis
+fn truncate(text: &str, width: usize) -> String {
+    let text = text.replace(['\n', '\r', '\t'], " ");
+    if text.chars().count() <= width {
+        return text;
     }
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
 }
 
-// Task struct to store task information
-#[derive(Debug, Clone)]
-struct Task {
-    id: u32,
-    title: String,
-    description: String,
-    due_date: Option<String>, // Consider using a proper date type in your implementation
-    status: TaskStatus,
+// Render tasks as a compact table with one aligned row per task
+fn render_task_table(tasks: &[&Task]) -> String {
+    let headers = ["ID", "Title", "Status", "Priority", "Due", "Tags"];
+    let rows: Vec<Vec<String>> = tasks.iter()
+        .map(|task| vec![
+            task.id.to_string(),
+            truncate(&task.title, MAX_TITLE_WIDTH),
+            task.status.to_string(),
+            task.priority.to_string(),
+            task.due_date.map(|date| date.format(DATE_FORMAT).to_string()).unwrap_or_default(),
+            truncate(&task.tags.join(", "), MAX_TAGS_WIDTH),
+        ])
+        .collect();
+    render_table(&headers, &rows)
 }
 
-impl Task {
-    fn display(&self) {
-        println!("Task #{}: {}", self.id, self.title);
-        println!("Status: {}", self.status);
-        println!("Description: {}", self.description);
-        if let Some(date) = &self.due_date {
-            println!("Due date: {}", date);
-        }
-        println!();
-    }
+// Render upcoming due dates as a table, marking the ones produced by a recurrence rule
+fn render_occurrence_table(occurrences: &[Occurrence]) -> String {
+    let headers = ["Date", "ID", "Title", "Priority", "Repeats"];
+    let rows: Vec<Vec<String>> = occurrences.iter()
+        .map(|occurrence| vec![
+            occurrence.date.format(DATE_FORMAT).to_string(),
+            occurrence.task.id.to_string(),
+            truncate(&occurrence.task.title, MAX_TITLE_WIDTH),
+            occurrence.task.priority.to_string(),
+            occurrence.task.recurrence.as_ref().map(Recurrence::to_string).unwrap_or_default(),
+        ])
+        .collect();
+    render_table(&headers, &rows)
+}
 
-    fn to_string(&self) -> String {
-        let status_str = match self.status {
-            TaskStatus::Pending => "pending",
-            TaskStatus::Completed => "completed",
-        };
-        
-        let due_date_str = match &self.due_date {
-            Some(date) => date.clone(),
-            None => String::from("none"),
-        };
-        
-        format!("{}|{}|{}|{}|{}", 
-            self.id, 
-            self.title, 
-            self.description, 
-            due_date_str,
-            status_str
-        )
-    }
-
-    fn from_string(s: &str) -> Option<Task> {
-        let parts: Vec<&str> = s.split('|').collect();
-        if parts.len() != 5 {
-            return None;
+// Align rows of cells under a header line, separating columns with two spaces
+fn render_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
         }
+    }
 
-        let id = parts[0].parse::<u32>().ok()?;
-        let title = parts[1].to_string();
-        let description = parts[2].to_string();
-        let due_date = if parts[3] == "none" {
-            None
-        } else {
-            Some(parts[3].to_string())
-        };
-        let status = match parts[4] {
-            "completed" => TaskStatus::Completed,
-            _ => TaskStatus::Pending,
-        };
+    let format_row = |cells: &[String]| {
+        let line: Vec<String> = cells.iter().zip(&widths)
+            .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        line.join("  ").trim_end().to_string()
+    };
 
-        Some(Task {
-            id,
-            title,
-            description,
-            due_date,
-            status,
-        })
+    let header_cells: Vec<String> = headers.iter().map(|header| header.to_string()).collect();
+    let rule: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+    let mut table = format_row(&header_cells);
+    table.push('\n');
+    table.push_str(&format_row(&rule));
+    for row in rows {
+        table.push('\n');
+        table.push_str(&format_row(row));
     }
+    table
 }
 
-// TaskManager to handle operations on tasks
-struct TaskManager {
-    tasks: Vec<Task>,
-    next_id: u32,
-}
-
-impl TaskManager {
-    // Create a new TaskManager
-    fn new() -> TaskManager {
-        TaskManager {
-            tasks: Vec::new(),
-            next_id: 1,
+// Render tasks and their subtasks as an indented tree, one line per task.
+// Pending dependencies are listed after the task that waits on them.
+fn render_task_tree(task_manager: &TaskManager, roots: &[&Task]) -> String {
+    fn render(task_manager: &TaskManager, task: &Task, prefix: &str, branch: &str, out: &mut String, seen: &mut HashSet<u32>) {
+        out.push_str(&format!("{}{}#{} {} [{}]", prefix, branch, task.id, task.title, task.status));
+        let blockers = task_manager.pending_dependencies(&task.depends_on);
+        if !blockers.is_empty() {
+            out.push_str(&format!(" (waiting on {})", format_ids(&blockers)));
         }
-    }
-    
-    // Add a new task to the task manager
-    fn add_task(&mut self, title: String, description: String, due_date: Option<String>) -> &Task {
-        let task = Task {
-            id: self.next_id,
-            title,
-            description,
-            due_date,
-            status: TaskStatus::Pending,
-        };
-        
-        self.next_id += 1;
-        self.tasks.push(task);
-        self.tasks.last().unwrap()
-    }
-    
-    // List all tasks
-    fn list_tasks(&self) {
-        if self.tasks.is_empty() {
-            println!("No tasks found.");
+        out.push('\n');
+        // A file edited by hand could contain a parent cycle; show each task only once
+        if !seen.insert(task.id) {
             return;
         }
 
-        println!("Task List:");
-        println!("----------");
-        for task in &self.tasks {
-            task.display();
+        let child_prefix = match branch {
+            "├── " => format!("{}│   ", prefix),
+            "└── " => format!("{}    ", prefix),
+            _ => prefix.to_string(),
+        };
+        let children = task_manager.subtasks(task.id);
+        for (i, child) in children.iter().enumerate() {
+            let branch = if i + 1 == children.len() { "└── " } else { "├── " };
+            render(task_manager, child, &child_prefix, branch, out, seen);
         }
     }
-    
-    // Find a task by ID
-    fn find_task(&self, id: u32) -> Option<&Task> {
-        self.tasks.iter().find(|task| task.id == id)
+
+    let mut out = String::new();
+    let mut seen = HashSet::new();
+    for root in roots {
+        render(task_manager, root, "", "", &mut out, &mut seen);
     }
-    
-    // Find a task by ID (mutable version)
-    fn find_task_mut(&mut self, id: u32) -> Option<&mut Task> {
-        self.tasks.iter_mut().find(|task| task.id == id)
+    out
+}
+
+// Print all details of a task
+fn display_task(task: &Task, now: DateTime<Local>) {
+    println!("Task #{}: {}", task.id, task.title);
+    println!("Status: {}", task.status);
+    println!("Description: {}", task.description);
+    if let Some(date) = &task.due_date {
+        println!("Due date: {}", date.format(DATE_FORMAT));
     }
-    
-    // Mark a task as complete
-    fn complete_task(&mut self, id: u32) -> Result<&Task, &'static str> {
-        let task = self.find_task_mut(id).ok_or("Task not found")?;
-        task.status = TaskStatus::Completed;
-        Ok(task)
+    println!("Priority: {}", task.priority);
+    if !task.tags.is_empty() {
+        println!("Tags: {}", task.tags.join(", "));
     }
-    
-    // Delete a task
-    fn delete_task(&mut self, id: u32) -> Result<(), &'static str> {
-        let position = self.tasks.iter().position(|task| task.id == id).ok_or("Task not found")?;
-        self.tasks.remove(position);
-        Ok(())
+    if let Some(recurrence) = &task.recurrence {
+        println!("Repeats: {}", recurrence.describe());
     }
-    
-    // Filter tasks by status
-    fn filter_by_status(&self, status: &TaskStatus) -> Vec<&Task> {
-        self.tasks.iter()
-            .filter(|task| task.status == *status)
-            .collect()
+    if let Some(parent) = task.parent {
+        println!("Subtask of: #{}", parent);
     }
-    
-    // Filter tasks by due date (simple string comparison)
-    fn filter_by_due_date(&self, due_date: &str) -> Vec<&Task> {
-        self.tasks.iter()
-            .filter(|task| {
-                if let Some(date) = &task.due_date {
-                    date == due_date
-                } else {
-                    false
-                }
-            })
-            .collect()
+    if !task.depends_on.is_empty() {
+        println!("Depends on: {}", format_ids(&task.depends_on));
     }
-    
-    // Save tasks to a file
-    fn save_to_file(&self, filename: &str) -> io::Result<()> {
-        let mut file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(filename)?;
-        
-        // Save the next_id at the top of the file
-        writeln!(file, "{}", self.next_id)?;
-        
-        // Save each task
-        for task in &self.tasks {
-            writeln!(file, "{}", task.to_string())?;
-        }
-        
-        println!("Tasks saved to '{}'", filename);
-        Ok(())
+    if let Some(created_at) = &task.created_at {
+        println!("Created: {}", created_at.format(TIME_FORMAT));
     }
-    
-    // Load tasks from a file
-    fn load_from_file(&mut self, filename: &str) -> io::Result<()> {
-        if !Path::new(filename).exists() {
-            return Err(io::Error::new(io::ErrorKind::NotFound, "File not found"));
-        }
-        
-        let mut file = File::open(filename)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        
-        let lines: Vec<&str> = contents.lines().collect();
-        if lines.is_empty() {
-            return Err(io::Error::new(io::ErrorKind::InvalidData, "File is empty"));
+    if let Some(completed_at) = &task.completed_at {
+        println!("Completed: {}", completed_at.format(TIME_FORMAT));
+    }
+    if !task.time_entries.is_empty() {
+        let spent = format_duration(task.time_spent(now));
+        match task.running_since() {
+            Some(start) => println!("Time spent: {} (running since {})", spent, start.format(TIME_FORMAT)),
+            None => println!("Time spent: {}", spent),
         }
-        
-        // First line is the next_id
-        self.next_id = lines[0].parse::<u32>().unwrap_or(1);
-        
-        // Clear existing tasks
-        self.tasks.clear();
-        
-        // Load tasks
-        for line in &lines[1..] {
-            if let Some(task) = Task::from_string(line) {
-                self.tasks.push(task);
-            }
+    }
+    if let Some(until) = task.snoozed_until.filter(|until| *until > now) {
+        println!("Reminders snoozed until: {}", until.format(TIME_FORMAT));
+    }
+    println!();
+}
+
+// Print a titled table of tasks, or a message when there are none
+fn display_task_list(heading: &str, tasks: &[&Task], empty_message: &str) {
+    if tasks.is_empty() {
+        println!("{}", empty_message);
+        return;
+    }
+
+    println!("{}", heading);
+    println!("{}", render_task_table(tasks));
+    println!();
+}
+
+// Mark the given byte ranges of `text`: bold on a terminal, otherwise in brackets
+fn highlight(text: &str, ranges: &[Range<usize>], ansi: bool) -> String {
+    let (open, close) = if ansi { ("\x1b[1m", "\x1b[0m") } else { ("[", "]") };
+    let mut out = String::new();
+    let mut last = 0;
+    for range in ranges {
+        out.push_str(&text[last..range.start]);
+        out.push_str(open);
+        out.push_str(&text[range.clone()]);
+        out.push_str(close);
+        last = range.end;
+    }
+    out.push_str(&text[last..]);
+    out.replace(['\n', '\r', '\t'], " ")
+}
+
+// Render search results, one line per task plus the description when it matched
+fn render_search_hits(hits: &[SearchHit], ansi: bool) -> String {
+    let mut out = String::new();
+    for hit in hits {
+        out.push_str(&format!(
+            "#{} {} ({})\n",
+            hit.task.id,
+            highlight(&hit.task.title, &hit.title_matches, ansi),
+            hit.task.status,
+        ));
+        if !hit.description_matches.is_empty() {
+            out.push_str(&format!("    {}\n", highlight(&hit.task.description, &hit.description_matches, ansi)));
         }
-        
-        println!("Loaded {} tasks from '{}'", self.tasks.len(), filename);
-        Ok(())
+    }
+    out
+}
+
+// Longest bar drawn by `render_bar_chart`
+const MAX_BAR_WIDTH: usize = 40;
+
+// Render labelled counts as horizontal bars of `#`, scaled so the largest count
+// fills MAX_BAR_WIDTH
+fn render_bar_chart(rows: &[(String, usize)]) -> String {
+    render_bar_chart_with(rows, |count| count.to_string())
+}
+
+// Like `render_bar_chart`, with each value written by `format_value`
+fn render_bar_chart_with(rows: &[(String, usize)], format_value: impl Fn(usize) -> String) -> String {
+    let label_width = rows.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+    let largest = rows.iter().map(|&(_, count)| count).max().unwrap_or(0).max(1);
+    let lines: Vec<String> = rows.iter()
+        .map(|&(ref label, count)| {
+            // Every non-zero count gets at least one mark
+            let width = (count * MAX_BAR_WIDTH).div_ceil(largest);
+            format!("{:<label_width$}  {} {}", label, "#".repeat(width), format_value(count), label_width = label_width)
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn display_stats(stats: &TaskStats) {
+    println!("Task Statistics:");
+    println!("----------------");
+    println!("Total tasks: {}", stats.total);
+    println!("Pending tasks: {}", stats.pending);
+    println!("Completed tasks: {}", stats.completed);
+    println!("Overdue tasks: {}", stats.overdue);
+    if let Some(rate) = stats.completion_rate() {
+        println!("Completion rate: {:.0}%", rate);
+    }
+    if let Some(average) = stats.average_time_to_complete {
+        println!("Average time to complete: {} (over {} tasks)", format_duration(average), stats.timed_completions);
     }
     
-    // Generate statistics about tasks
-    fn generate_stats(&self) -> HashMap<String, usize> {
-        let mut stats = HashMap::new();
-        
-        stats.insert("total".to_string(), self.tasks.len());
-        stats.insert("pending".to_string(), 
-            self.tasks.iter().filter(|t| t.status == TaskStatus::Pending).count());
-        stats.insert("completed".to_string(), 
-            self.tasks.iter().filter(|t| t.status == TaskStatus::Completed).count());
-            
-        // Count tasks by due date
-        let mut dates = HashMap::new();
-        for task in &self.tasks {
-            if let Some(date) = &task.due_date {
-                *dates.entry(date.clone()).or_insert(0) += 1;
-            }
-        }
+    if !stats.by_week.is_empty() {
+        let rows: Vec<(String, usize)> = stats.by_week.iter()
+            .map(|(week_start, &count)| (week_start.format("%G-W%V (from %b %d)").to_string(), count))
+            .collect();
+        println!();
+        println!("Due by week:");
+        println!("{}", render_bar_chart(&rows));
+    }
+    if !stats.by_date.is_empty() {
+        let rows: Vec<(String, usize)> = stats.by_date.iter()
+            .map(|(date, &count)| (date.format(DATE_FORMAT).to_string(), count))
+            .collect();
+        println!();
+        println!("Due by date:");
+        println!("{}", render_bar_chart(&rows));
+    }
+    if !stats.time_by_task.is_empty() {
+        let total: Duration = stats.time_by_task.iter().map(|tracked| tracked.total).sum();
+        let rows: Vec<Vec<String>> = stats.time_by_task.iter()
+            .map(|tracked| vec![
+                tracked.id.to_string(),
+                truncate(&tracked.title, MAX_TITLE_WIDTH),
+                format_duration(tracked.total),
+                if tracked.running { "running".to_string() } else { String::new() },
+            ])
+            .collect();
+        println!();
+        println!("Time tracked: {}", format_duration(total));
+        println!("{}", render_table(&["ID", "Title", "Time", "Timer"], &rows));
         
-        stats
+        let rows: Vec<(String, usize)> = stats.time_by_day.iter()
+            .map(|(date, duration)| (date.format(DATE_FORMAT).to_string(), duration.num_minutes().max(0) as usize))
+            .collect();
+        println!();
+        println!("Time by day:");
+        println!("{}", render_bar_chart_with(&rows, |minutes| format_duration(Duration::minutes(minutes as i64))));
+    }
+    println!();
+}
+
+fn display_reminders(reminders: &[Reminder], today: NaiveDate) {
+    println!("Reminders:");
+    for reminder in reminders {
+        println!("  {}", reminder.describe(today));
     }
 }
 
-// Command enum to represent user commands
-enum Command {
-    Add { title: String, description: String, due_date: Option<String> },
-    List,
-    ListPending,
-    ListCompleted,
-    Complete { id: u32 },
-    Delete { id: u32 },
-    Save { filename: String },
-    Load { filename: String },
-    Stats,
-    Help,
+// Whether the caller should keep reading commands after one has run
+enum Flow {
+    Continue,
     Quit,
-    Unknown,
 }
 
-// Parse a command from user input
-fn parse_command(input: &str) -> Command {
-    let parts: Vec<&str> = input.trim().split_whitespace().collect();
-    
-    if parts.is_empty() {
-        return Command::Unknown;
-    }
-    
-    match parts[0].to_lowercase().as_str() {
-        "add" => {
-            if parts.len() < 3 {
-                println!("Error: 'add' command requires a title and description");
-                return Command::Unknown;
+// Run a single command against the task manager, printing its output.
+// Errors are returned so the REPL and the command-line mode can report them differently.
+fn execute_command(task_manager: &mut TaskManager, command: Command, clock: &dyn Clock) -> Result<Flow, Box<dyn Error>> {
+    let modifies_tasks = command.modifies_tasks();
+    let now = clock.now();
+    let today = now.date_naive();
+    match command {
+        Command::Add { task } => {
+            let task = task_manager.add_task(task, now)?;
+            println!("Task added with ID: {}", task.id);
+        },
+        Command::List { filter, format, upcoming: Some(until), .. } => {
+            let mut occurrences = task_manager.upcoming(today, until);
+            occurrences.retain(|occurrence| filter.matches(occurrence.task));
+            match format {
+                OutputFormat::Json => {
+                    let json = serde_json::to_string_pretty(&occurrences)?;
+                    println!("{}", json);
+                },
+                OutputFormat::Text if occurrences.is_empty() => {
+                    println!("Nothing due by {}.", until.format(DATE_FORMAT));
+                },
+                OutputFormat::Text => {
+                    println!("Upcoming until {}:", until.format(DATE_FORMAT));
+                    println!("{}", render_occurrence_table(&occurrences));
+                    println!();
+                },
             }
-            let title = parts[1].to_string();
-            let description = parts[2].to_string();
-            let due_date = if parts.len() > 3 {
-                Some(parts[3].to_string())
-            } else {
-                None
+        },
+        Command::List { filter, sort, format, upcoming: None } => {
+            let mut tasks = task_manager.filter(&filter);
+            sort.sort(&mut tasks);
+            match format {
+                OutputFormat::Json => {
+                    let json = serde_json::to_string_pretty(&tasks)?;
+                    println!("{}", json);
+                },
+                OutputFormat::Text if filter.is_empty() => display_task_list("Task List:", &tasks, "No tasks found."),
+                OutputFormat::Text => display_task_list("Matching Tasks:", &tasks, "No matching tasks found."),
+            }
+        },
+        Command::Show { id } => {
+            display_task(task_manager.find_task(id).ok_or(TaskError::NotFound(id))?, now);
+        },
+        Command::Tree { root } => {
+            let roots: Vec<&Task> = match root {
+                Some(id) => vec![task_manager.find_task(id).ok_or(TaskError::NotFound(id))?],
+                // Tasks whose parent no longer exists are shown at the top level
+                None => task_manager.tasks().iter()
+                    .filter(|task| task.parent.and_then(|parent| task_manager.find_task(parent)).is_none())
+                    .collect(),
             };
-            Command::Add { title, description, due_date }
-        },
-        "list" => Command::List,
-        "pending" => Command::ListPending,
-        "completed" => Command::ListCompleted,
-        "complete" => {
-            if parts.len() < 2 {
-                println!("Error: 'complete' command requires a task ID");
-                return Command::Unknown;
+            if roots.is_empty() {
+                println!("No tasks found.");
+            } else {
+                print!("{}", render_task_tree(task_manager, &roots));
             }
-            match parts[1].parse::<u32>() {
-                Ok(id) => Command::Complete { id },
-                Err(_) => {
-                    println!("Error: Task ID must be a positive number");
-                    Command::Unknown
-                }
+        },
+        Command::Search { query } => {
+            let hits = task_manager.search(&query);
+            if hits.is_empty() {
+                println!("No tasks match '{}'.", query);
+            } else {
+                println!("Search results for '{}':", query);
+                print!("{}", render_search_hits(&hits, io::stdout().is_terminal()));
+                println!();
+            }
+        },
+        Command::ListPending => {
+            let pending_tasks = task_manager.filter_by_status(&TaskStatus::Pending);
+            display_task_list("Pending Tasks:", &pending_tasks, "No pending tasks found.");
+        },
+        Command::ListCompleted => {
+            let completed_tasks = task_manager.filter_by_status(&TaskStatus::Completed);
+            display_task_list("Completed Tasks:", &completed_tasks, "No completed tasks found.");
+        },
+        Command::Overdue => {
+            let overdue_tasks = task_manager.overdue_tasks(today);
+            display_task_list("Overdue Tasks:", &overdue_tasks, "No overdue tasks found.");
+        },
+        Command::DueToday => {
+            let due_tasks = task_manager.filter_by_due_date(today);
+            display_task_list("Tasks Due Today:", &due_tasks, "No tasks due today.");
+        },
+        Command::DueBefore { date } => {
+            let due_tasks = task_manager.filter_due_before(date);
+            let heading = format!("Tasks Due Before {}:", date.format(DATE_FORMAT));
+            display_task_list(&heading, &due_tasks, "No tasks found.");
+        },
+        Command::Complete { id } => {
+            let next = task_manager.complete_task(id, now)?;
+            println!("Task #{} marked as completed", id);
+            if let Some(next) = next {
+                let due = next.due_date.map(|date| date.format(DATE_FORMAT).to_string()).unwrap_or_default();
+                println!("Next occurrence added as task #{}, due {}", next.id, due);
+            }
+        },
+        Command::Start { id, parallel } => {
+            task_manager.start_timer(id, now, parallel)?;
+            println!("Timer started for task #{}", id);
+        },
+        Command::Stop { id } => {
+            let duration = task_manager.stop_timer(id, now)?;
+            println!("Timer stopped for task #{} after {}", id, format_duration(duration));
+        },
+        Command::Edit { id, update } => {
+            let (changed, next) = task_manager.update_task(id, update, now)?;
+            if changed.is_empty() {
+                println!("Task #{} unchanged", id);
+            } else {
+                println!("Task #{} updated: {}", id, changed.join(", "));
+            }
+            if let Some(next) = next {
+                let due = next.due_date.map(|date| date.format(DATE_FORMAT).to_string()).unwrap_or_default();
+                println!("Next occurrence added as task #{}, due {}", next.id, due);
+            }
+            if let Some(task) = task_manager.find_task(id) {
+                display_task(task, now);
+            }
+        },
+        Command::Delete { id } => {
+            task_manager.delete_task(id)?;
+            println!("Task #{} deleted", id);
+        },
+        Command::Move { id, project } => {
+            task_manager.move_task(id, &project)?;
+            println!("Task #{} moved to project '{}'", id, project);
+        },
+        Command::Project { action: ProjectAction::List } => {
+            let rows: Vec<Vec<String>> = task_manager.projects().into_iter()
+                .map(|(name, tasks)| {
+                    let marker = if name == task_manager.project() { "*" } else { "" };
+                    let pending = tasks.iter().filter(|task| task.status == TaskStatus::Pending).count();
+                    vec![marker.to_string(), name.to_string(), pending.to_string(), tasks.len().to_string()]
+                })
+                .collect();
+            println!("Projects (* is current):");
+            println!("{}", render_table(&["", "Project", "Pending", "Total"], &rows));
+            println!();
+        },
+        Command::Project { action: ProjectAction::Create { name } } => {
+            task_manager.create_project(&name)?;
+            println!("Project '{}' created; use 'project switch {}' to work in it", name, name);
+        },
+        Command::Project { action: ProjectAction::Switch { name } } => {
+            task_manager.switch_project(&name)?;
+            println!("Switched to project '{}' ({} tasks)", name, task_manager.tasks().len());
+        },
+        Command::Project { action: ProjectAction::Rename { name, new_name } } => {
+            task_manager.rename_project(&name, &new_name)?;
+            println!("Project '{}' renamed to '{}'", name, new_name);
+        },
+        Command::Project { action: ProjectAction::Delete { name, force } } => {
+            let count = task_manager.delete_project(&name, force)?;
+            println!("Project '{}' deleted along with {} tasks", name, count);
+        },
+        Command::Undo => {
+            let description = task_manager.undo()?;
+            println!("Undid {}", description);
+        },
+        Command::Redo => {
+            let description = task_manager.redo()?;
+            println!("Redid {}", description);
+        },
+        Command::Save { filename, format } => {
+            task_manager.save_to_file(&filename, format)
+                .map_err(|e| format!("Could not save to '{}': {}", filename, e))?;
+            println!("Tasks saved to '{}'", filename);
+        },
+        Command::Load { filename, strict } => {
+            let report = task_manager.load_from_file(&filename, strict)
+                .map_err(|e| format!("Could not load from '{}': {}", filename, e))?;
+            println!("Loaded {} tasks from '{}'", report.loaded, filename);
+            for warning in report.warnings() {
+                println!("  {}", warning);
+            }
+            if task_manager.autosaving() {
+                println!("Autosave now saves to '{}'", filename);
             }
         },
-        "delete" => {
-            if parts.len() < 2 {
-                println!("Error: 'delete' command requires a task ID");
-                return Command::Unknown;
+        Command::Migrate { source, destination, strict } => {
+            let report = migrate_file(&source, &destination, strict)
+                .map_err(|e| format!("Could not migrate '{}': {}", source, e))?;
+            println!("Migrated {} tasks from '{}' to '{}'", report.loaded, source, destination);
+            for warning in report.warnings() {
+                println!("  {}", warning);
             }
-            match parts[1].parse::<u32>() {
-                Ok(id) => Command::Delete { id },
-                Err(_) => {
-                    println!("Error: Task ID must be a positive number");
-                    Command::Unknown
+        },
+        Command::Export { filename, format } => {
+            let contents = export_tasks(task_manager.tasks(), format, now);
+            write_atomically(&filename, &contents)
+                .map_err(|e| format!("Could not export to '{}': {}", filename, e))?;
+            println!("Exported {} tasks to '{}'", task_manager.tasks().len(), filename);
+        },
+        Command::Import { filename, format, mapping } => {
+            let contents = fs::read_to_string(&filename)
+                .map_err(|e| format!("Could not import from '{}': {}", filename, e))?;
+            let report = import_tasks(&contents, format, &mapping)
+                .map_err(|e| format!("Could not import from '{}': {}", filename, e))?;
+            let count = task_manager.import_tasks(report.tasks, now)?;
+            println!("Imported {} tasks from '{}'", count, filename);
+            if !report.failures.is_empty() {
+                println!("{} entries could not be imported:", report.failures.len());
+                for failure in &report.failures {
+                    println!("  line {}: {}", failure.line, failure.reason);
                 }
             }
         },
-        "save" => {
-            if parts.len() < 2 {
-                println!("Error: 'save' command requires a filename");
-                return Command::Unknown;
+        Command::Autosave { file: Some(file) } => {
+            let report = task_manager.enable_autosave(&file)
+                .map_err(|e| format!("Could not enable autosave to '{}': {}", file, e))?;
+            if let Some(report) = report {
+                println!("Loaded {} tasks from '{}'", report.loaded, file);
+                for warning in report.warnings() {
+                    println!("  {}", warning);
+                }
             }
-            Command::Save { filename: parts[1].to_string() }
+            println!("Autosave enabled: tasks are saved to '{}' after every change", file);
         },
-        "load" => {
-            if parts.len() < 2 {
-                println!("Error: 'load' command requires a filename");
-                return Command::Unknown;
+        Command::Autosave { file: None } => {
+            task_manager.set_storage(None);
+            println!("Autosave disabled");
+        },
+        Command::Reminders { enabled: None, lead_time: None } => {
+            let reminders = task_manager.reminders(now);
+            if reminders.is_empty() {
+                println!("No reminders.");
+            } else {
+                display_reminders(&reminders, today);
             }
-            Command::Load { filename: parts[1].to_string() }
         },
-        "stats" => Command::Stats,
-        "help" => Command::Help,
-        "quit" | "exit" => Command::Quit,
-        _ => Command::Unknown,
+        Command::Reminders { enabled, lead_time } => {
+            let settings = &mut task_manager.reminders;
+            settings.enabled = enabled.unwrap_or(settings.enabled);
+            settings.lead_time = lead_time.unwrap_or(settings.lead_time);
+            if settings.enabled {
+                println!("Reminders on, from {} before due dates", format_duration(settings.lead_time));
+            } else {
+                println!("Reminders off");
+            }
+        },
+        Command::Snooze { id, span } => {
+            let until = span
+                .map(|span| now.checked_add_signed(span).ok_or(TaskError::SpanOutOfRange(format_duration(span))))
+                .transpose()?;
+            task_manager.snooze(id, until)?;
+            match until {
+                Some(until) => println!("Reminders for task #{} snoozed until {}", id, until.format(TIME_FORMAT)),
+                None => println!("Reminders for task #{} are no longer snoozed", id),
+            }
+        },
+        Command::Stats => {
+            display_stats(&task_manager.generate_stats(now));
+        },
+        Command::Help => {
+            display_help();
+        },
+        Command::Quit => {
+            println!("Goodbye!");
+            return Ok(Flow::Quit);
+        },
+        Command::Unknown => {
+            println!("Unknown command. Type 'help' for a list of commands.");
+        },
     }
+    
+    if modifies_tasks {
+        task_manager.autosave()
+            .map_err(|e| format!("Autosave failed, your changes are not saved: {}", e))?;
+    }
+    Ok(Flow::Continue)
 }
 
-fn display_help() {
-    println!("Task Manager - Available Commands:");
-    println!("----------------------------------");
-    println!("add <title> <description> [due_date] - Add a new task");
-    println!("list - List all tasks");
-    println!("pending - List pending tasks");
-    println!("completed - List completed tasks");
-    println!("complete <id> - Mark a task as completed");
-    println!("delete <id> - Delete a task");
-    println!("save <filename> - Save tasks to a file");
-    println!("load <filename> - Load tasks from a file");
-    println!("stats - Show task statistics");
-    println!("help - Display this help message");
-    println!("quit - Exit the program");
-    println!();
-}
-
-fn main() {
-    // Initialize task manager
-    let mut task_manager = TaskManager::new();
-    
+// Interactive loop reading commands from stdin. Reminders are shown at startup and,
+// as tasks come due, before each prompt.
+fn run_repl(mut task_manager: TaskManager, clock: &dyn Clock) {
     println!("Welcome to the Rust Task Manager!");
     println!("Type 'help' for a list of commands.");
     
     // Main application loop
     loop {
-        // Get user input
-        print!("> ");
+        let now = clock.now();
+        let reminders = task_manager.new_reminders(now);
+        if !reminders.is_empty() {
+            display_reminders(&reminders, now.date_naive());
+        }
+        
+        // Get user input; the prompt names the project unless it is the default one
+        if task_manager.project() == DEFAULT_PROJECT {
+            print!("> ");
+        } else {
+            print!("{}> ", task_manager.project());
+        }
         io::stdout().flush().unwrap();
         
         let mut input = String::new();
-        io::stdin().read_line(&mut input).expect("Failed to read line");
+        let bytes_read = io::stdin().read_line(&mut input).expect("Failed to read line");
+        if bytes_read == 0 {
+            // End of input (e.g. Ctrl-D or a piped script)
+            println!();
+            break;
+        }
         
-        let command = parse_command(&input);
+        let command = match parse_command(&input) {
+            Ok(command) => command,
+            Err(e) => {
+                println!("{}", e.render(&input));
+                continue;
+            }
+        };
         
         // Process command
-        match command {
-            Command::Add { title, description, due_date } => {
-                let task = task_manager.add_task(title, description, due_date);
-                println!("Task added with ID: {}", task.id);
-            },
-            Command::List => {
-                task_manager.list_tasks();
-            },
-            Command::ListPending => {
-                let pending_tasks = task_manager.filter_by_status(&TaskStatus::Pending);
-                if pending_tasks.is_empty() {
-                    println!("No pending tasks found.");
-                } else {
-                    println!("Pending Tasks:");
-                    println!("--------------");
-                    for task in pending_tasks {
-                        task.display();
-                    }
-                }
-            },
-            Command::ListCompleted => {
-                let completed_tasks = task_manager.filter_by_status(&TaskStatus::Completed);
-                if completed_tasks.is_empty() {
-                    println!("No completed tasks found.");
-                } else {
-                    println!("Completed Tasks:");
-                    println!("----------------");
-                    for task in completed_tasks {
-                        task.display();
-                    }
-                }
-            },
-            Command::Complete { id } => {
-                match task_manager.complete_task(id) {
-                    Ok(_) => println!("Task #{} marked as completed", id),
-                    Err(e) => println!("Error: {}", e),
-                }
-            },
-            Command::Delete { id } => {
-                match task_manager.delete_task(id) {
-                    Ok(_) => println!("Task #{} deleted", id),
-                    Err(e) => println!("Error: {}", e),
-                }
-            },
-            Command::Save { filename } => {
-                match task_manager.save_to_file(&filename) {
-                    Ok(_) => {},
-                    Err(e) => println!("Error saving to file: {}", e),
-                }
-            },
-            Command::Load { filename } => {
-                match task_manager.load_from_file(&filename) {
-                    Ok(_) => {},
-                    Err(e) => println!("Error loading from file: {}", e),
-                }
-            },
-            Command::Stats => {
-                let stats = task_manager.generate_stats();
-                println!("Task Statistics:");
-                println!("----------------");
-                println!("Total tasks: {}", stats.get("total").unwrap_or(&0));
-                println!("Pending tasks: {}", stats.get("pending").unwrap_or(&0));
-                println!("Completed tasks: {}", stats.get("completed").unwrap_or(&0));
-                println!();
-            },
-            Command::Help => {
-                display_help();
-            },
-            Command::Quit => {
-                println!("Goodbye!");
-                break;
-            },
-            Command::Unknown => {
-                println!("Unknown command. Type 'help' for a list of commands.");
-            },
+        match execute_command(&mut task_manager, command, clock) {
+            Ok(Flow::Continue) => {},
+            Ok(Flow::Quit) => break,
+            Err(e) => println!("Error: {}", e),
         }
     }
-}
\ No newline at end of file
+}
+
+// Exit code for command-line usage errors, as opposed to failed commands
+const EXIT_USAGE: u8 = 2;
+
+// Run a single command given on the command line, e.g.
+// `module5_final add --title "Buy milk" --description "Oat milk" --file tasks.json`.
+// Tasks are loaded from `--file` (if it exists) and autosaved back to it after a change;
+// `.db`, `.sqlite` and `.sqlite3` files are SQLite databases. With only `--file`, the REPL starts on that file.
+// With `--strict`, a file with lines that cannot be loaded is an error.
+fn run_cli(argv: &[String]) -> ExitCode {
+    let CommandLine { file, strict, command } = match parse_argv(argv) {
+        Ok(command_line) => command_line,
+        Err(e) => {
+            eprintln!("{}", e.render(&argv.join(" ")));
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    let command = match command {
+        Command::Unknown if file.is_some() => None,
+        Command::Unknown => {
+            eprintln!("Error: no command given. Run 'module5_final help' for usage.");
+            return ExitCode::from(EXIT_USAGE);
+        },
+        command => Some(command),
+    };
+    
+    let mut task_manager = TaskManager::new();
+    match &file {
+        Some(file) => {
+            // Changes are written back through autosave
+            match task_manager.open_file(file, strict) {
+                Ok(report) => {
+                    for warning in report.iter().flat_map(LoadReport::warnings) {
+                        eprintln!("Warning: '{}': {}", file, warning);
+                    }
+                },
+                Err(e) => {
+                    eprintln!("Error: Could not load from '{}': {}", file, e);
+                    return ExitCode::FAILURE;
+                },
+            }
+        },
+        None if command.as_ref().is_some_and(Command::modifies_tasks) => {
+            eprintln!("Warning: no --file given, the change will not be saved");
+        },
+        None => {},
+    }
+    
+    let Some(command) = command else {
+        run_repl(task_manager, &SystemClock);
+        return ExitCode::SUCCESS;
+    };
+    if let Err(e) = execute_command(&mut task_manager, command, &SystemClock) {
+        eprintln!("Error: {}", e);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+fn main() -> ExitCode {
+    let argv: Vec<String> = std::env::args().skip(1).collect();
+    if argv.is_empty() {
+        run_repl(TaskManager::new(), &SystemClock);
+        ExitCode::SUCCESS
+    } else {
+        run_cli(&argv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(row: &[&str]) -> Vec<String> {
+        row.iter().map(|cell| cell.to_string()).collect()
+    }
+
+    #[test]
+    fn tables_align_columns_to_their_widest_cell() {
+        let rows = [cells(&["1", "Buy milk", "High"]), cells(&["12", "Call", ""])];
+        let table = render_table(&["ID", "Title", "Priority"], &rows);
+        assert_eq!(table, "ID  Title     Priority\n\
+                           --  --------  --------\n\
+                           1   Buy milk  High\n\
+                           12  Call");
+    }
+
+    #[test]
+    fn long_cells_are_truncated_by_characters() {
+        assert_eq!(truncate("Buy milk", 8), "Buy milk");
+        assert_eq!(truncate("Buy oat milk", 8), "Buy oat…");
+        assert_eq!(truncate("Café au lait", 5), "Café…");
+        assert_eq!(truncate("Two\nlines", 10), "Two lines");
+    }
+}
@@ -0,0 +1,940 @@
+// Parsing of commands typed at the prompt or given on the command line
+
+use std::path::Path;
+use chrono::{Duration, NaiveDate};
+
+use super::exchange::{CsvField, ExchangeFormat};
+use super::query::{Comparison, FilterTerm, SortKey, TaskFilter, TaskSort};
+use super::storage::StorageFormat;
+use super::task::{parse_due_date, parse_span, parse_tags, today, NewTask, Priority, Recurrence, TaskStatus, TaskUpdate};
+
+// File used by `autosave` when no filename is given
+pub const DEFAULT_TASK_FILE: &str = "tasks.json";
+
+// How listings are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+// Subcommands of `project`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProjectAction {
+    List,
+    Create { name: String },
+    Switch { name: String },
+    Rename { name: String, new_name: String },
+    // `force` also deletes the project's tasks
+    Delete { name: String, force: bool },
+}
+
+// Command enum to represent user commands
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Add { task: NewTask },
+    // With `upcoming`, due dates up to that date are listed instead of tasks
+    List { filter: TaskFilter, sort: TaskSort, format: OutputFormat, upcoming: Option<NaiveDate> },
+    Show { id: u32 },
+    // `None` shows every top-level task
+    Tree { root: Option<u32> },
+    Search { query: String },
+    ListPending,
+    ListCompleted,
+    Overdue,
+    DueToday,
+    DueBefore { date: NaiveDate },
+    Complete { id: u32 },
+    // `parallel` allows other timers to keep running
+    Start { id: u32, parallel: bool },
+    Stop { id: u32 },
+    Edit { id: u32, update: TaskUpdate },
+    Delete { id: u32 },
+    Move { id: u32, project: String },
+    Project { action: ProjectAction },
+    Undo,
+    Redo,
+    Save { filename: String, format: StorageFormat },
+    // In strict mode, any line that cannot be loaded fails the command
+    Load { filename: String, strict: bool },
+    Migrate { source: String, destination: String, strict: bool },
+    Export { filename: String, format: ExchangeFormat },
+    // `mapping` assigns CSV columns (by header) to task fields
+    Import { filename: String, format: ExchangeFormat, mapping: Vec<(String, CsvField)> },
+    // `None` turns autosave off
+    Autosave { file: Option<String> },
+    // Without arguments, lists the current reminders
+    Reminders { enabled: Option<bool>, lead_time: Option<Duration> },
+    // `None` ends a snooze
+    Snooze { id: u32, span: Option<Duration> },
+    Stats,
+    Help,
+    Quit,
+    Unknown,
+}
+
+impl Command {
+    // Whether running the command changes the tasks (and so needs saving)
+    pub fn modifies_tasks(&self) -> bool {
+        matches!(
+            self,
+            Command::Add { .. } | Command::Complete { .. } | Command::Edit { .. } | Command::Delete { .. }
+                | Command::Start { .. } | Command::Stop { .. } | Command::Snooze { .. } | Command::Undo | Command::Redo | Command::Import { .. } | Command::Move { .. }
+        ) || matches!(self, Command::Project { action } if !matches!(action, ProjectAction::List))
+    }
+}
+
+// Error found while parsing a command line, pointing at a character column of the input
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(position: usize, message: impl Into<String>) -> ParseError {
+        ParseError { position, message: message.into() }
+    }
+
+    // Render the error with the input line and a caret under the offending position
+    pub fn render(&self, input: &str) -> String {
+        let line = input.trim_end_matches(['\n', '\r']);
+        format!("Error: {}\n  {}\n  {}^", self.message, line, " ".repeat(self.position))
+    }
+}
+
+// A word of the command line, with quotes and escapes resolved
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    text: String,
+    position: usize,
+}
+
+// A `--key=value` or `--key value` flag, or a bare `--key`
+#[derive(Debug, Clone, PartialEq)]
+struct Flag {
+    name: String,
+    value: Option<String>,
+    position: usize,
+}
+
+// Boolean flags, which never take the following command-line argument as their value
+const SWITCHES: &[&str] = &["desc", "strict", "force", "parallel"];
+
+// A tokenized command line: positional words and flags
+#[derive(Debug)]
+struct Args {
+    words: Vec<Token>,
+    flags: Vec<Flag>,
+    next_word: usize,
+    end: usize,
+}
+
+// Split a command line into words the way a shell would.
+// Double quotes allow `\"`, `\\`, `\n` and `\t` escapes, single quotes are literal,
+// and a backslash outside quotes escapes the next character. Words starting with an
+// unquoted `--` are flags, until a bare `--` marks the rest of the line as words.
+fn tokenize(input: &str) -> Result<Args, ParseError> {
+    let chars: Vec<char> = input.trim_end_matches(['\n', '\r']).chars().collect();
+    let mut words = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let is_flag = chars[i..].starts_with(&['-', '-']);
+        let mut text = String::new();
+        while i < chars.len() && !chars[i].is_whitespace() {
+            match chars[i] {
+                '"' => {
+                    let quote = i;
+                    i += 1;
+                    loop {
+                        match chars.get(i) {
+                            None => return Err(ParseError::new(quote, "Unterminated double quote")),
+                            Some('"') => break,
+                            Some('\\') => {
+                                let escaped = *chars.get(i + 1)
+                                    .ok_or_else(|| ParseError::new(i, "Unfinished escape sequence"))?;
+                                text.push(match escaped {
+                                    'n' => '\n',
+                                    't' => '\t',
+                                    other => other,
+                                });
+                                i += 2;
+                            },
+                            Some(&c) => {
+                                text.push(c);
+                                i += 1;
+                            },
+                        }
+                    }
+                    i += 1;
+                },
+                '\'' => {
+                    let quote = i;
+                    let close = chars[i + 1..].iter().position(|&c| c == '\'')
+                        .ok_or_else(|| ParseError::new(quote, "Unterminated single quote"))?;
+                    text.extend(&chars[i + 1..i + 1 + close]);
+                    i += close + 2;
+                },
+                '\\' => {
+                    let escaped = *chars.get(i + 1)
+                        .ok_or_else(|| ParseError::new(i, "Unfinished escape sequence"))?;
+                    text.push(escaped);
+                    i += 2;
+                },
+                c => {
+                    text.push(c);
+                    i += 1;
+                },
+            }
+        }
+
+        words.push((Token { text, position: start }, is_flag));
+    }
+
+    Ok(Args::new(words, chars.len()))
+}
+
+impl Args {
+    // Sort split words into positional words and flags. `is_flag` marks words that start
+    // with `--`, which are flags until a bare `--`. Flags may be written `--key=value` or
+    // `--key value`, except for switches, which never take the following word.
+    fn new(split: Vec<(Token, bool)>, end: usize) -> Args {
+        let mut words = Vec::new();
+        let mut flags = Vec::new();
+        let mut flags_allowed = true;
+        let mut split = split.into_iter().peekable();
+
+        while let Some((token, is_flag)) = split.next() {
+            if !flags_allowed || !is_flag {
+                words.push(token);
+            } else if token.text == "--" {
+                flags_allowed = false;
+            } else if let Some((name, value)) = token.text[2..].split_once('=') {
+                flags.push(Flag { name: name.to_lowercase(), value: Some(value.to_string()), position: token.position });
+            } else {
+                let name = token.text[2..].to_lowercase();
+                let value = split.next_if(|(_, next_is_flag)| !next_is_flag && !SWITCHES.contains(&name.as_str()))
+                    .map(|(value, _)| value.text);
+                flags.push(Flag { name, value, position: token.position });
+            }
+        }
+
+        Args { words, flags, next_word: 0, end }
+    }
+
+    // Build arguments from the program's command line, which the shell has already split.
+    // Positions refer to the arguments joined with single spaces.
+    fn from_argv(argv: &[String]) -> Args {
+        let mut split = Vec::new();
+        let mut position = 0;
+        for arg in argv {
+            split.push((Token { text: arg.clone(), position }, arg.starts_with("--")));
+            position += arg.chars().count() + 1;
+        }
+        Args::new(split, position.saturating_sub(1))
+    }
+
+    // Take the next positional word, or fail with `missing` at the end of the input
+    fn word(&mut self, missing: &str) -> Result<Token, ParseError> {
+        self.optional_word()
+            .ok_or_else(|| ParseError::new(self.end, missing))
+    }
+
+    fn optional_word(&mut self) -> Option<Token> {
+        let token = self.words.get(self.next_word).cloned();
+        if token.is_some() {
+            self.next_word += 1;
+        }
+        token
+    }
+
+    // Take the next positional word as a task ID
+    fn id(&mut self, missing: &str) -> Result<u32, ParseError> {
+        let token = self.word(missing)?;
+        token.text.parse::<u32>()
+            .map_err(|_| ParseError::new(token.position, "Task ID must be a positive number"))
+    }
+
+    // Remove a flag and return its value; flags given without a value are an error
+    fn flag(&mut self, name: &str) -> Result<Option<Token>, ParseError> {
+        let Some(index) = self.flags.iter().position(|flag| flag.name == name) else {
+            return Ok(None);
+        };
+        let flag = self.flags.remove(index);
+        match flag.value {
+            Some(text) => Ok(Some(Token { text, position: flag.position })),
+            None => Err(ParseError::new(flag.position, format!("Flag '--{}' requires a value (--{}=...)", name, name))),
+        }
+    }
+
+    // Remove a boolean flag such as `--desc`, which may also be written `--desc=true|false`
+    fn switch(&mut self, name: &str) -> Result<bool, ParseError> {
+        let Some(index) = self.flags.iter().position(|flag| flag.name == name) else {
+            return Ok(false);
+        };
+        let flag = self.flags.remove(index);
+        match flag.value.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("true") => Ok(true),
+            Some("false") => Ok(false),
+            Some(_) => Err(ParseError::new(flag.position, format!("Flag '--{}' does not take a value", name))),
+        }
+    }
+
+    // A positional word, or the named flag if the word was not given
+    fn word_or_flag(&mut self, name: &str) -> Result<Option<Token>, ParseError> {
+        match self.flag(name)? {
+            Some(token) => Ok(Some(token)),
+            None => Ok(self.optional_word()),
+        }
+    }
+
+    // Reject any word or flag the command did not consume
+    fn finish(self) -> Result<(), ParseError> {
+        if let Some(flag) = self.flags.first() {
+            return Err(ParseError::new(flag.position, format!("Unknown flag '--{}'", flag.name)));
+        }
+        if let Some(token) = self.words.get(self.next_word) {
+            return Err(ParseError::new(token.position, format!("Unexpected argument '{}'", token.text)));
+        }
+        Ok(())
+    }
+}
+
+fn parse_date_token(token: &Token) -> Result<NaiveDate, ParseError> {
+    parse_due_date(&token.text, today()).map_err(|e| ParseError::new(token.position, e.to_string()))
+}
+
+fn parse_status_token(token: &Token) -> Result<TaskStatus, ParseError> {
+    TaskStatus::parse(&token.text).ok_or_else(|| {
+        ParseError::new(token.position, format!("Unknown status '{}', expected pending or completed", token.text))
+    })
+}
+
+fn parse_priority_token(token: &Token) -> Result<Priority, ParseError> {
+    Priority::parse(&token.text).ok_or_else(|| {
+        ParseError::new(token.position, format!("Unknown priority '{}', expected low, medium, high or urgent", token.text))
+    })
+}
+
+// Parse a comma-separated list of task IDs, e.g. `3,5`
+fn parse_ids_token(token: &Token) -> Result<Vec<u32>, ParseError> {
+    let mut ids = Vec::new();
+    for id in token.text.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        let id = id.parse::<u32>()
+            .map_err(|_| ParseError::new(token.position, format!("Invalid task ID '{}'", id)))?;
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    Ok(ids)
+}
+
+fn parse_recurrence_token(token: &Token) -> Result<Recurrence, ParseError> {
+    Recurrence::parse(&token.text).map_err(|e| ParseError::new(token.position, e))
+}
+
+fn parse_tags_token(token: &Token) -> Result<Vec<String>, ParseError> {
+    parse_tags(&token.text).map_err(|e| ParseError::new(token.position, e))
+}
+
+// Parse one `list` query term: a field, an operator (`:`, `=`, `!=`, `<`, `<=`, `>`, `>=`)
+// and a value, e.g. `status:pending`, `tag:work`, `priority>=high` or `due<2026-11-01`
+fn parse_filter_term(token: &Token) -> Result<FilterTerm, ParseError> {
+    let text = &token.text;
+    let op_start = text.find([':', '=', '!', '<', '>']).ok_or_else(|| {
+        ParseError::new(token.position, format!("Invalid filter '{}', expected e.g. status:pending or priority>=high", text))
+    })?;
+    let (field, rest) = text.split_at(op_start);
+    let (comparison, op_len) = match rest {
+        _ if rest.starts_with("<=") => (Comparison::LessOrEqual, 2),
+        _ if rest.starts_with(">=") => (Comparison::GreaterOrEqual, 2),
+        _ if rest.starts_with("!=") => (Comparison::NotEqual, 2),
+        _ if rest.starts_with('<') => (Comparison::Less, 1),
+        _ if rest.starts_with('>') => (Comparison::Greater, 1),
+        _ if rest.starts_with(':') || rest.starts_with('=') => (Comparison::Equal, 1),
+        _ => return Err(ParseError::new(token.position + field.chars().count(), "Invalid filter operator")),
+    };
+    let value = Token {
+        text: rest[op_len..].to_string(),
+        position: token.position + field.chars().count() + op_len,
+    };
+    if value.text.is_empty() {
+        return Err(ParseError::new(value.position, format!("Filter '{}' requires a value", field)));
+    }
+    let equality_only = || {
+        if matches!(comparison, Comparison::Equal | Comparison::NotEqual) {
+            Ok(())
+        } else {
+            Err(ParseError::new(token.position + field.chars().count(), format!("'{}' filters only support ':' and '!='", field)))
+        }
+    };
+
+    match field.to_lowercase().as_str() {
+        "status" => {
+            equality_only()?;
+            Ok(FilterTerm::Status(comparison, parse_status_token(&value)?))
+        },
+        "tag" => {
+            equality_only()?;
+            Ok(FilterTerm::Tag(comparison, value.text))
+        },
+        "priority" => Ok(FilterTerm::Priority(comparison, parse_priority_token(&value)?)),
+        "due" if value.text.eq_ignore_ascii_case("none") => {
+            equality_only()?;
+            Ok(FilterTerm::Due(comparison, None))
+        },
+        "due" => Ok(FilterTerm::Due(comparison, Some(parse_date_token(&value)?))),
+        _ => Err(ParseError::new(
+            token.position,
+            format!("Unknown filter field '{}', expected status, tag, priority or due", field),
+        )),
+    }
+}
+
+// Parse a command from user input
+pub fn parse_command(input: &str) -> Result<Command, ParseError> {
+    parse_args(tokenize(input)?)
+}
+
+// A program command line: the command with the global `--file` and `--strict` flags
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandLine {
+    pub file: Option<String>,
+    pub strict: bool,
+    pub command: Command,
+}
+
+// Parse the program's command line, which the shell has already split into arguments.
+// Error positions refer to the arguments joined with single spaces.
+pub fn parse_argv(argv: &[String]) -> Result<CommandLine, ParseError> {
+    let mut args = Args::from_argv(argv);
+    let file = args.flag("file")?.map(|token| token.text);
+    let strict = args.switch("strict")?;
+    let command = match parse_args(args)? {
+        // `--strict` was taken as the global flag, but also applies to the command
+        Command::Load { filename, strict: load_strict } => Command::Load { filename, strict: strict || load_strict },
+        Command::Migrate { source, destination, strict: migrate_strict } => {
+            Command::Migrate { source, destination, strict: strict || migrate_strict }
+        },
+        command => command,
+    };
+    Ok(CommandLine { file, strict, command })
+}
+
+// Build a command from tokenized arguments, whether typed in the REPL or given on the command line
+fn parse_args(mut args: Args) -> Result<Command, ParseError> {
+    let Some(name) = args.optional_word() else {
+        return Ok(Command::Unknown);
+    };
+    
+    let command = match name.text.to_lowercase().as_str() {
+        "add" => {
+            let title = args.word_or_flag("title")?
+                .ok_or_else(|| ParseError::new(args.end, "'add' command requires a title and description"))?;
+            let description = args.word_or_flag("description")?
+                .ok_or_else(|| ParseError::new(args.end, "'add' command requires a title and description"))?;
+            let due_date = match args.word_or_flag("due")? {
+                Some(token) => Some(parse_date_token(&token)?),
+                None => None,
+            };
+            let priority = match args.flag("priority")? {
+                Some(token) => parse_priority_token(&token)?,
+                None => Priority::default(),
+            };
+            let tags = match args.flag("tags")? {
+                Some(token) => parse_tags_token(&token)?,
+                None => Vec::new(),
+            };
+            let recurrence = match args.flag("repeat")? {
+                Some(token) => Some(parse_recurrence_token(&token)?),
+                None => None,
+            };
+            let parent = match args.flag("parent")? {
+                Some(token) => Some(token.text.parse::<u32>()
+                    .map_err(|_| ParseError::new(token.position, "Task ID must be a positive number"))?),
+                None => None,
+            };
+            let depends_on = match args.flag("depends")? {
+                Some(token) => parse_ids_token(&token)?,
+                None => Vec::new(),
+            };
+            let task = NewTask {
+                title: title.text,
+                description: description.text,
+                due_date,
+                priority,
+                tags,
+                recurrence,
+                parent,
+                depends_on,
+            };
+            Command::Add { task }
+        },
+        "list" => {
+            let mut filter = TaskFilter::default();
+            if let Some(token) = args.flag("status")? {
+                filter.terms.push(FilterTerm::Status(Comparison::Equal, parse_status_token(&token)?));
+            }
+            while let Some(token) = args.optional_word() {
+                filter.terms.push(parse_filter_term(&token)?);
+            }
+            let format = match args.flag("format")? {
+                None => OutputFormat::Text,
+                Some(token) => match token.text.to_lowercase().as_str() {
+                    "text" => OutputFormat::Text,
+                    "json" => OutputFormat::Json,
+                    _ => return Err(ParseError::new(
+                        token.position,
+                        format!("Unknown format '{}', expected text or json", token.text),
+                    )),
+                },
+            };
+            let mut sort = TaskSort::default();
+            if let Some(token) = args.flag("sort")? {
+                sort.key = SortKey::parse(&token.text).ok_or_else(|| {
+                    ParseError::new(token.position, format!("Unknown sort key '{}', expected due, priority, id or title", token.text))
+                })?;
+            }
+            sort.descending = args.switch("desc")?;
+            // `--upcoming=14d` lists due dates over the next 14 days; a date also works
+            let upcoming = match args.flag("upcoming")? {
+                Some(mut token) => {
+                    if token.text.starts_with(|c: char| c.is_ascii_digit()) && !token.text.contains('-') {
+                        token.text.insert(0, '+');
+                    }
+                    Some(parse_date_token(&token)?)
+                },
+                None => None,
+            };
+            Command::List { filter, sort, format, upcoming }
+        },
+        "pending" => Command::ListPending,
+        "completed" => Command::ListCompleted,
+        "overdue" => Command::Overdue,
+        "due-today" => Command::DueToday,
+        "due-before" => {
+            let token = args.word("'due-before' command requires a date")?;
+            Command::DueBefore { date: parse_date_token(&token)? }
+        },
+        "show" => Command::Show { id: args.id("'show' command requires a task ID")? },
+        "search" => {
+            let mut terms = vec![args.word("'search' command requires at least one word")?.text];
+            while let Some(token) = args.optional_word() {
+                terms.push(token.text);
+            }
+            Command::Search { query: terms.join(" ") }
+        },
+        "tree" => {
+            let root = match args.optional_word() {
+                Some(token) => Some(token.text.parse::<u32>()
+                    .map_err(|_| ParseError::new(token.position, "Task ID must be a positive number"))?),
+                None => None,
+            };
+            Command::Tree { root }
+        },
+        "complete" => Command::Complete { id: args.id("'complete' command requires a task ID")? },
+        "start" => Command::Start {
+            id: args.id("'start' command requires a task ID")?,
+            parallel: args.switch("parallel")?,
+        },
+        "stop" => Command::Stop { id: args.id("'stop' command requires a task ID")? },
+        "edit" => {
+            let id = args.id("'edit' command requires a task ID")?;
+            let mut update = TaskUpdate {
+                title: args.flag("title")?.map(|token| token.text),
+                description: args.flag("description")?.map(|token| token.text),
+                ..TaskUpdate::default()
+            };
+            if let Some(token) = args.flag("due")? {
+                update.due_date = Some(if token.text.eq_ignore_ascii_case("none") {
+                    None
+                } else {
+                    Some(parse_date_token(&token)?)
+                });
+            }
+            if let Some(token) = args.flag("status")? {
+                update.status = Some(parse_status_token(&token)?);
+            }
+            if let Some(token) = args.flag("priority")? {
+                update.priority = Some(parse_priority_token(&token)?);
+            }
+            if let Some(token) = args.flag("tags")? {
+                update.tags = Some(parse_tags_token(&token)?);
+            }
+            if let Some(token) = args.flag("repeat")? {
+                update.recurrence = Some(if token.text.eq_ignore_ascii_case("none") {
+                    None
+                } else {
+                    Some(parse_recurrence_token(&token)?)
+                });
+            }
+            if let Some(token) = args.flag("parent")? {
+                update.parent = Some(if token.text.eq_ignore_ascii_case("none") {
+                    None
+                } else {
+                    Some(token.text.parse::<u32>()
+                        .map_err(|_| ParseError::new(token.position, "Task ID must be a positive number"))?)
+                });
+            }
+            if let Some(token) = args.flag("depends")? {
+                update.depends_on = Some(if token.text.eq_ignore_ascii_case("none") {
+                    Vec::new()
+                } else {
+                    parse_ids_token(&token)?
+                });
+            }
+            if update.is_empty() {
+                return Err(ParseError::new(
+                    args.end,
+                    "'edit' command requires at least one of --title, --description, --due, --status, --priority, --tags, --repeat, --parent or --depends",
+                ));
+            }
+            Command::Edit { id, update }
+        },
+        "delete" => Command::Delete { id: args.id("'delete' command requires a task ID")? },
+        "move" => {
+            let id = args.id("'move' command requires a task ID and a project")?;
+            let project = args.word("'move' command requires a task ID and a project")?.text;
+            Command::Move { id, project }
+        },
+        "project" => {
+            let action = match args.optional_word() {
+                None => ProjectAction::List,
+                Some(action) => match action.text.to_lowercase().as_str() {
+                    "list" => ProjectAction::List,
+                    "create" => ProjectAction::Create { name: args.word("'project create' requires a name")?.text },
+                    "switch" => ProjectAction::Switch { name: args.word("'project switch' requires a name")?.text },
+                    "rename" => {
+                        let name = args.word("'project rename' requires the current and the new name")?.text;
+                        let new_name = args.word("'project rename' requires the current and the new name")?.text;
+                        ProjectAction::Rename { name, new_name }
+                    },
+                    "delete" => {
+                        let name = args.word("'project delete' requires a name")?.text;
+                        ProjectAction::Delete { name, force: args.switch("force")? }
+                    },
+                    other => return Err(ParseError::new(
+                        action.position,
+                        format!("Unknown project action '{}', expected list, create, switch, rename or delete", other),
+                    )),
+                },
+            };
+            Command::Project { action }
+        },
+        "undo" => Command::Undo,
+        "redo" => Command::Redo,
+        "save" => {
+            let filename = args.word("'save' command requires a filename")?.text;
+            let format = match args.flag("format")? {
+                None => StorageFormat::from_path(&filename),
+                Some(token) => StorageFormat::parse(&token.text).ok_or_else(|| {
+                    ParseError::new(token.position, format!("Unknown format '{}', expected json, pipe or sqlite", token.text))
+                })?,
+            };
+            Command::Save { filename, format }
+        },
+        "load" => {
+            let filename = args.word("'load' command requires a filename")?.text;
+            Command::Load { filename, strict: args.switch("strict")? }
+        },
+        "migrate" => {
+            let source = args.word("'migrate' command requires a filename")?.text;
+            let destination = match args.optional_word() {
+                Some(destination) => destination.text,
+                None => Path::new(&source).with_extension("json").to_string_lossy().into_owned(),
+            };
+            Command::Migrate { source, destination, strict: args.switch("strict")? }
+        },
+        "export" | "import" => {
+            let filename = args.word(&format!("'{}' command requires a filename", name.text))?.text;
+            let format = match args.flag("format")? {
+                Some(token) => ExchangeFormat::parse(&token.text).ok_or_else(|| {
+                    ParseError::new(token.position, format!("Unknown format '{}', expected csv, md or ics", token.text))
+                })?,
+                None => ExchangeFormat::from_path(&filename).ok_or_else(|| {
+                    ParseError::new(args.end, "Cannot tell the format from the file extension, add --format=csv|md|ics")
+                })?,
+            };
+            if name.text.eq_ignore_ascii_case("export") {
+                Command::Export { filename, format }
+            } else {
+                let mut mapping = Vec::new();
+                if let Some(token) = args.flag("map")? {
+                    for pair in token.text.split(',').filter(|pair| !pair.trim().is_empty()) {
+                        let field = pair.rsplit_once('=')
+                            .and_then(|(header, field)| Some((header.to_string(), CsvField::from_header(field)?)));
+                        mapping.push(field.ok_or_else(|| ParseError::new(
+                            token.position,
+                            format!("Invalid mapping '{}', expected <header>=title|description|due|status|priority|tags|ignore", pair),
+                        ))?);
+                    }
+                }
+                Command::Import { filename, format, mapping }
+            }
+        },
+        "autosave" => {
+            let file = match args.optional_word() {
+                Some(token) if token.text.eq_ignore_ascii_case("off") => None,
+                Some(token) if token.text.eq_ignore_ascii_case("on") => Some(DEFAULT_TASK_FILE.to_string()),
+                Some(token) => Some(token.text),
+                None => Some(DEFAULT_TASK_FILE.to_string()),
+            };
+            Command::Autosave { file }
+        },
+        "reminders" => {
+            let enabled = match args.optional_word() {
+                None => None,
+                Some(token) => match token.text.to_lowercase().as_str() {
+                    "on" => Some(true),
+                    "off" => Some(false),
+                    _ => return Err(ParseError::new(token.position, format!("Expected on or off but found '{}'", token.text))),
+                },
+            };
+            let lead_time = match args.flag("lead")? {
+                Some(token) => Some(parse_span(&token.text).map_err(|e| ParseError::new(token.position, e.to_string()))?),
+                None => None,
+            };
+            Command::Reminders { enabled, lead_time }
+        },
+        "snooze" => {
+            let id = args.id("'snooze' command requires a task ID")?;
+            // A snooze lasts a day unless told otherwise
+            let span = match args.optional_word() {
+                Some(token) if token.text.eq_ignore_ascii_case("none") => None,
+                Some(token) => Some(parse_span(&token.text).map_err(|e| ParseError::new(token.position, e.to_string()))?),
+                None => Some(Duration::days(1)),
+            };
+            Command::Snooze { id, span }
+        },
+        "stats" => Command::Stats,
+        "help" => Command::Help,
+        "quit" | "exit" => Command::Quit,
+        other => {
+            return Err(ParseError::new(
+                name.position,
+                format!("Unknown command '{}'. Type 'help' for a list of commands.", other),
+            ));
+        },
+    };
+    
+    args.finish()?;
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn words(input: &str) -> Vec<String> {
+        tokenize(input).unwrap().words.into_iter().map(|token| token.text).collect()
+    }
+
+    // Quote a word so that `tokenize` reads it back unchanged
+    fn quote(word: &str) -> String {
+        format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    #[test]
+    fn add_takes_positional_words_or_flags() {
+        let expected = Command::Add {
+            task: NewTask {
+                title: "Buy milk".to_string(),
+                description: "Oat, not dairy".to_string(),
+                due_date: NaiveDate::from_ymd_opt(2026, 10, 20),
+                priority: Priority::High,
+                tags: vec!["home".to_string(), "shop".to_string()],
+                ..NewTask::default()
+            },
+        };
+        assert_eq!(
+            parse_command("add \"Buy milk\" 'Oat, not dairy' 2026-10-20 --priority=high --tags=home,shop\n"),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            parse_command("ADD --title=Buy\\ milk --description=\"Oat, not dairy\" --due=2026-10-20 --tags=home,shop --priority=HIGH"),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn list_collects_filters_and_sorting() {
+        let Ok(Command::List { filter, sort, format, upcoming }) =
+            parse_command("list tag:work priority>=high --sort=due --desc --format=json")
+        else {
+            panic!("expected a list command");
+        };
+        assert_eq!(
+            filter.terms,
+            [FilterTerm::Tag(Comparison::Equal, "work".to_string()), FilterTerm::Priority(Comparison::GreaterOrEqual, Priority::High)]
+        );
+        assert_eq!(sort, TaskSort { key: SortKey::Due, descending: true });
+        assert_eq!(format, OutputFormat::Json);
+        assert_eq!(upcoming, None);
+    }
+
+    #[test]
+    fn flag_values_may_follow_a_space() {
+        let Ok(Command::List { sort, .. }) = parse_command("list --sort due --desc") else {
+            panic!("expected a list command");
+        };
+        assert_eq!(sort, TaskSort { key: SortKey::Due, descending: true });
+        assert_eq!(parse_command("list --desc --sort due"), parse_command("list --sort=due --desc"));
+        assert_eq!(parse_command("add a b --priority high"), parse_command("add a b --priority=high"));
+
+        // Switches never take the next word, so it stays a filter
+        let Ok(Command::List { filter, sort, .. }) = parse_command("list --desc tag:work") else {
+            panic!("expected a list command");
+        };
+        assert!(sort.descending);
+        assert_eq!(filter.terms, [FilterTerm::Tag(Comparison::Equal, "work".to_string())]);
+        assert_eq!(parse_command("list --sort").unwrap_err().position, 5);
+    }
+
+    #[test]
+    fn upcoming_takes_a_length_of_time_or_a_date() {
+        let upcoming = |input| match parse_command(input) {
+            Ok(Command::List { upcoming, .. }) => upcoming,
+            other => panic!("expected a list command, got {:?}", other),
+        };
+        assert_eq!(upcoming("list --upcoming 14d"), Some(today() + Duration::days(14)));
+        assert_eq!(upcoming("list --upcoming=2w"), Some(today() + Duration::days(14)));
+        assert_eq!(upcoming("list --upcoming 2026-12-31"), NaiveDate::from_ymd_opt(2026, 12, 31));
+        assert_eq!(upcoming("list"), None);
+        assert_eq!(parse_command("list --upcoming soon").unwrap_err().position, 5);
+    }
+
+    #[test]
+    fn edit_needs_at_least_one_change() {
+        let Ok(Command::Edit { id, update }) = parse_command("edit 3 --due=none --repeat=weekly:mon") else {
+            panic!("expected an edit command");
+        };
+        assert_eq!(id, 3);
+        assert_eq!(update.due_date, Some(None));
+        assert_eq!(update.recurrence, Some(Some(Recurrence::Weekly { weekdays: vec![chrono::Weekday::Mon] })));
+        assert!(parse_command("edit 3").is_err());
+    }
+
+    #[test]
+    fn other_commands_parse() {
+        assert_eq!(parse_command(""), Ok(Command::Unknown));
+        assert_eq!(parse_command("complete 4"), Ok(Command::Complete { id: 4 }));
+        assert_eq!(parse_command("start 4 --parallel"), Ok(Command::Start { id: 4, parallel: true }));
+        assert_eq!(parse_command("snooze 4"), Ok(Command::Snooze { id: 4, span: Some(Duration::days(1)) }));
+        assert_eq!(parse_command("snooze 4 none"), Ok(Command::Snooze { id: 4, span: None }));
+        assert_eq!(parse_command("autosave off"), Ok(Command::Autosave { file: None }));
+        assert_eq!(
+            parse_command("project delete old --force"),
+            Ok(Command::Project { action: ProjectAction::Delete { name: "old".to_string(), force: true } })
+        );
+        assert_eq!(
+            parse_command("save tasks.db"),
+            Ok(Command::Save { filename: "tasks.db".to_string(), format: StorageFormat::Sqlite })
+        );
+        assert_eq!(
+            parse_command("migrate old.txt"),
+            Ok(Command::Migrate { source: "old.txt".to_string(), destination: "old.json".to_string(), strict: false })
+        );
+        assert_eq!(
+            parse_command("import todo.csv --map=Summary=title,Notes=ignore"),
+            Ok(Command::Import {
+                filename: "todo.csv".to_string(),
+                format: ExchangeFormat::Csv,
+                mapping: vec![("Summary".to_string(), CsvField::Title), ("Notes".to_string(), CsvField::Ignore)],
+            })
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_word() {
+        let error = parse_command("complete soon").unwrap_err();
+        assert_eq!(error.position, 9);
+        assert_eq!(error.render("complete soon"), "Error: Task ID must be a positive number\n  complete soon\n           ^");
+
+        assert_eq!(parse_command("list --colour=red").unwrap_err().position, 5);
+        assert_eq!(parse_command("add \"Unclosed").unwrap_err(), ParseError::new(4, "Unterminated double quote"));
+        assert_eq!(parse_command("frobnicate").unwrap_err().position, 0);
+    }
+
+    #[test]
+    fn bad_due_dates_are_errors_rather_than_panics() {
+        assert_eq!(parse_command("add a b +3é").unwrap_err().position, 8);
+        assert_eq!(
+            parse_command("add a b +99999999999999d").unwrap_err().message,
+            "Date '+99999999999999d' is out of range"
+        );
+        assert_eq!(
+            parse_command("edit 1 --due=+2000000000000000000w").unwrap_err().message,
+            "Date '+2000000000000000000w' is out of range"
+        );
+    }
+
+    #[test]
+    fn bad_spans_are_errors_rather_than_panics() {
+        assert_eq!(parse_command("snooze 1 3é").unwrap_err().position, 9);
+        assert_eq!(
+            parse_command("reminders --lead=99999999999999999m").unwrap_err().message,
+            "Length of time '99999999999999999m' is out of range"
+        );
+    }
+
+    #[test]
+    fn tokenizer_handles_quotes_escapes_and_the_end_of_flags() {
+        assert_eq!(words(r#"a "b c" 'd "e"' f\ g "\t\"\\""#), ["a", "b c", "d \"e\"", "f g", "\t\"\\"]);
+        let args = tokenize("add -- --title x").unwrap();
+        assert!(args.flags.is_empty());
+        assert_eq!(args.words.len(), 3);
+    }
+
+    #[test]
+    fn command_lines_take_global_file_and_strict_flags() {
+        let command_line = parse_argv(&argv(&["load", "old.txt", "--file", "tasks.db", "--strict"])).unwrap();
+        assert_eq!(command_line.file.as_deref(), Some("tasks.db"));
+        assert!(command_line.strict);
+        assert_eq!(command_line.command, Command::Load { filename: "old.txt".to_string(), strict: true });
+
+        let command_line = parse_argv(&argv(&["add", "Buy milk", "Oat milk"])).unwrap();
+        assert_eq!(command_line.file, None);
+        assert!(matches!(command_line.command, Command::Add { task } if task.title == "Buy milk"));
+
+        // Positions count the arguments joined with spaces
+        assert_eq!(parse_argv(&argv(&["add", "Buy milk", "Oat", "--colour=red"])).unwrap_err().position, 17);
+    }
+
+    // Command lines that get past the command name, with dates and lengths of time
+    // that have amounts of any size and units that may be any character
+    fn command_lines() -> impl Strategy<Value = String> {
+        let name = prop::sample::select(vec!["add a b", "edit 1", "list", "reminders", "snooze 1"]);
+        let amount = || ("[+-]?", "[0-9]{1,20}", prop_oneof!["[dwmh]?", "\\PC"]).prop_map(|(sign, digits, unit)| sign + &digits + &unit);
+        let word = prop_oneof![
+            1 => "\\PC{0,12}",
+            2 => amount(),
+            2 => ("--(due|upcoming|lead|sort|repeat|priority|tags)=", amount()).prop_map(|(flag, value)| flag + &value),
+        ];
+        (name, prop::collection::vec(word, 0..4)).prop_map(|(name, words)| format!("{} {}", name, words.join(" ")))
+    }
+
+    proptest! {
+        #[test]
+        fn quoted_words_tokenize_back_unchanged(original in prop::collection::vec(any::<String>(), 0..5)) {
+            let line: Vec<String> = original.iter().map(|word| quote(word)).collect();
+            prop_assert_eq!(words(&line.join(" ")), original);
+        }
+
+        #[test]
+        fn parsing_never_panics(input in prop_oneof!["\\PC{0,40}", command_lines()]) {
+            let _ = parse_command(&input);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+// Errors returned by TaskManager operations
+
+use std::error::Error;
+use std::fmt;
+
+use super::manager::DEFAULT_PROJECT;
+use super::task::format_ids;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskError {
+    NotFound(u32),
+    ParentNotFound(u32),
+    EmptyTitle,
+    OwnParent(u32),
+    // Making `parent` the parent of `id` would create a loop of subtasks
+    SubtaskLoop { id: u32, parent: u32 },
+    OwnDependency(u32),
+    // The dependency chain leading back to the first task
+    DependencyCycle(Vec<u32>),
+    Blocked { id: u32, blockers: Vec<u32> },
+    Completed(u32),
+    AlreadyRunning(u32),
+    NotRunning(u32),
+    // Tasks whose timers are already running
+    TimersRunning(Vec<u32>),
+    NothingToUndo,
+    NothingToRedo,
+    InvalidProjectName(String),
+    ProjectNotFound(String),
+    ProjectExists(String),
+    AlreadyInProject { id: u32, project: String },
+    CannotRenameDefault,
+    CannotDeleteDefault,
+    CurrentProject(String),
+    ProjectNotEmpty { name: String, tasks: usize },
+    InvalidDate(String),
+    InvalidRelativeDate(String),
+    DateOutOfRange(String),
+    InvalidSpan(String),
+    SpanOutOfRange(String),
+    // The highest ID is never given to a task, so that next_id can always move past it
+    IdOutOfRange(u32),
+}
+
+impl fmt::Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TaskError::NotFound(id) => write!(f, "Task #{} not found", id),
+            TaskError::ParentNotFound(id) => write!(f, "Parent task #{} not found", id),
+            TaskError::EmptyTitle => write!(f, "Title cannot be empty"),
+            TaskError::OwnParent(id) => write!(f, "Task #{} cannot be its own parent", id),
+            TaskError::SubtaskLoop { id, parent } => {
+                write!(f, "Task #{} cannot become a subtask of its own subtask #{}", id, parent)
+            },
+            TaskError::OwnDependency(id) => write!(f, "Task #{} cannot depend on itself", id),
+            TaskError::DependencyCycle(cycle) => {
+                let cycle: Vec<String> = cycle.iter().map(|id| format!("#{}", id)).collect();
+                write!(f, "Dependency cycle: {}", cycle.join(" -> "))
+            },
+            TaskError::Blocked { id, blockers } => {
+                write!(f, "Task #{} is waiting on pending tasks {}", id, format_ids(blockers))
+            },
+            TaskError::Completed(id) => write!(f, "Task #{} is completed", id),
+            TaskError::AlreadyRunning(id) => write!(f, "Task #{} is already running", id),
+            TaskError::NotRunning(id) => write!(f, "Task #{} is not running", id),
+            TaskError::TimersRunning(ids) => {
+                write!(f, "Already running: {}; stop it first or start with --parallel", format_ids(ids))
+            },
+            TaskError::NothingToUndo => write!(f, "Nothing to undo"),
+            TaskError::NothingToRedo => write!(f, "Nothing to redo"),
+            TaskError::InvalidProjectName(name) => {
+                write!(f, "Invalid project name '{}': use letters, digits, '-' and '_'", name)
+            },
+            TaskError::ProjectNotFound(name) => write!(f, "Project '{}' not found", name),
+            TaskError::ProjectExists(name) => write!(f, "Project '{}' already exists", name),
+            TaskError::AlreadyInProject { id, project } => {
+                write!(f, "Task #{} is already in project '{}'", id, project)
+            },
+            TaskError::CannotRenameDefault => write!(f, "The '{}' project cannot be renamed", DEFAULT_PROJECT),
+            TaskError::CannotDeleteDefault => write!(f, "The '{}' project cannot be deleted", DEFAULT_PROJECT),
+            TaskError::CurrentProject(name) => {
+                write!(f, "Project '{}' is current; switch to another project first", name)
+            },
+            TaskError::ProjectNotEmpty { name, tasks } => {
+                write!(f, "Project '{}' has {} tasks; use --force to delete them too", name, tasks)
+            },
+            TaskError::InvalidDate(input) => {
+                write!(f, "Invalid date '{}', expected YYYY-MM-DD, today, tomorrow or +Nd", input)
+            },
+            TaskError::InvalidRelativeDate(input) => {
+                write!(f, "Invalid relative date '{}', expected e.g. +3d or +2w", input)
+            },
+            TaskError::DateOutOfRange(input) => write!(f, "Date '{}' is out of range", input),
+            TaskError::InvalidSpan(input) => {
+                write!(f, "Invalid length of time '{}', expected e.g. 30m, 2h, 3d or 1w", input)
+            },
+            TaskError::SpanOutOfRange(input) => write!(f, "Length of time '{}' is out of range", input),
+            TaskError::IdOutOfRange(id) => {
+                write!(f, "Task ID #{} is out of range, the highest is #{}", id, u32::MAX - 1)
+            },
+        }
+    }
+}
+
+impl Error for TaskError {}
@@ -0,0 +1,712 @@
+// Import and export of tasks as CSV, Markdown and iCalendar
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use chrono::{DateTime, Duration, Local, NaiveDate};
+
+use super::task::{format_duration, parse_due_date, parse_tags, today, NewTask, Priority, Task, TaskStatus, DATE_FORMAT};
+
+// Formats for exchanging tasks with other tools. Unlike task files, these hold no IDs
+// or next_id: imported tasks are added to the current list with new IDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExchangeFormat {
+    // One row per task under a header row
+    Csv,
+    // A checklist of `- [ ]` and `- [x]` items
+    Markdown,
+    // VTODO components of an iCalendar file
+    ICalendar,
+}
+
+impl ExchangeFormat {
+    pub fn from_path(filename: &str) -> Option<ExchangeFormat> {
+        let extension = Path::new(filename).extension()?.to_str()?;
+        ExchangeFormat::parse(extension)
+    }
+
+    pub fn parse(name: &str) -> Option<ExchangeFormat> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(ExchangeFormat::Csv),
+            "md" | "markdown" => Some(ExchangeFormat::Markdown),
+            "ics" | "ical" | "icalendar" => Some(ExchangeFormat::ICalendar),
+            _ => None,
+        }
+    }
+}
+
+// Task fields a CSV column can be imported into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvField {
+    Title,
+    Description,
+    Due,
+    Status,
+    Priority,
+    Tags,
+    Ignore,
+}
+
+impl CsvField {
+    // Recognize a column by its header, including the names other tools commonly use
+    pub fn from_header(header: &str) -> Option<CsvField> {
+        match header.trim().to_lowercase().replace(['_', '-'], " ").as_str() {
+            "title" | "name" | "task" | "task name" | "summary" | "subject" => Some(CsvField::Title),
+            "description" | "notes" | "details" | "body" => Some(CsvField::Description),
+            "due" | "due date" | "deadline" => Some(CsvField::Due),
+            "status" | "state" | "done" | "completed" => Some(CsvField::Status),
+            "priority" => Some(CsvField::Priority),
+            "tags" | "labels" | "categories" => Some(CsvField::Tags),
+            "id" | "time spent minutes" | "ignore" => Some(CsvField::Ignore),
+            _ => None,
+        }
+    }
+}
+
+// A task read from an import file, before it is given an ID
+#[derive(Debug, Clone)]
+pub struct ImportedTask {
+    pub task: NewTask,
+    pub status: TaskStatus,
+}
+
+// A row or entry of an import file that could not be turned into a task
+#[derive(Debug, Clone)]
+pub struct ImportFailure {
+    pub line: usize,
+    pub reason: String,
+}
+
+// Tasks read from an import file, and the rows that failed
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub tasks: Vec<ImportedTask>,
+    pub failures: Vec<ImportFailure>,
+}
+
+impl ImportReport {
+    pub fn push(&mut self, line: usize, row: Result<ImportedTask, String>) {
+        match row {
+            Ok(task) => self.tasks.push(task),
+            Err(reason) => self.failures.push(ImportFailure { line, reason }),
+        }
+    }
+}
+
+// Write tasks in an exchange format. `now` stamps iCalendar entries.
+pub fn export_tasks(tasks: &[Task], format: ExchangeFormat, now: DateTime<Local>) -> String {
+    match format {
+        ExchangeFormat::Csv => export_csv(tasks, now),
+        ExchangeFormat::Markdown => export_markdown(tasks, now),
+        ExchangeFormat::ICalendar => export_icalendar(tasks, now),
+    }
+}
+
+// Read tasks from an exchange format. `mapping` assigns CSV columns to fields by header,
+// overriding the built-in header names. Errors mean nothing could be imported at all.
+pub fn import_tasks(contents: &str, format: ExchangeFormat, mapping: &[(String, CsvField)]) -> Result<ImportReport, String> {
+    match format {
+        ExchangeFormat::Csv => import_csv(contents, mapping),
+        ExchangeFormat::Markdown => Ok(import_markdown(contents)),
+        ExchangeFormat::ICalendar => import_icalendar(contents),
+    }
+}
+
+fn parse_status_value(value: &str) -> Result<TaskStatus, String> {
+    match value.trim().to_lowercase().as_str() {
+        "" | "no" | "false" | "todo" => Ok(TaskStatus::Pending),
+        "done" | "yes" | "true" | "x" => Ok(TaskStatus::Completed),
+        other => TaskStatus::parse(other).ok_or_else(|| format!("Unknown status '{}'", value.trim())),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn export_csv(tasks: &[Task], now: DateTime<Local>) -> String {
+    let mut out = String::from("id,title,description,due,status,priority,tags,time_spent_minutes\n");
+    for task in tasks {
+        let row = [
+            task.id.to_string(),
+            task.title.clone(),
+            task.description.clone(),
+            task.due_date.map(|date| date.format(DATE_FORMAT).to_string()).unwrap_or_default(),
+            task.status.to_string().to_lowercase(),
+            task.priority.to_string().to_lowercase(),
+            task.tags.join(","),
+            task.time_spent(now).num_minutes().to_string(),
+        ];
+        let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+// Split CSV text into records, each with the line it starts on. Quoted fields may
+// contain commas, doubled quotes and line breaks.
+fn parse_csv_records(contents: &str) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            },
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {},
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                // Blank lines separate nothing and are skipped
+                if record.len() > 1 || !record[0].is_empty() {
+                    records.push((record_line, std::mem::take(&mut record)));
+                }
+                record.clear();
+                line += 1;
+                record_line = line;
+            },
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            },
+        }
+    }
+    if in_quotes {
+        return Err(format!("Unterminated quoted field starting on line {}", record_line));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((record_line, record));
+    }
+    Ok(records)
+}
+
+fn import_csv(contents: &str, mapping: &[(String, CsvField)]) -> Result<ImportReport, String> {
+    let mut records = parse_csv_records(contents)?.into_iter();
+    let (_, headers) = records.next().ok_or("File is empty")?;
+    let columns: Vec<Option<CsvField>> = headers.iter()
+        .map(|header| {
+            mapping.iter()
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case(header.trim()))
+                .map(|&(_, field)| field)
+                .or_else(|| CsvField::from_header(header))
+        })
+        .collect();
+    if !columns.contains(&Some(CsvField::Title)) {
+        return Err(format!(
+            "No title column among the headers ({}); map one with --map=<header>=title",
+            headers.join(", ")
+        ));
+    }
+
+    let today = today();
+    let mut report = ImportReport::default();
+    for (line, record) in records {
+        let row = (|| {
+            if record.len() != headers.len() {
+                return Err(format!("Expected {} fields but found {}", headers.len(), record.len()));
+            }
+            let mut task = NewTask::default();
+            let mut status = TaskStatus::Pending;
+            for (field, value) in columns.iter().zip(&record) {
+                match field {
+                    Some(CsvField::Title) => task.title = value.trim().to_string(),
+                    Some(CsvField::Description) => task.description = value.clone(),
+                    Some(CsvField::Due) if value.trim().is_empty() => {},
+                    Some(CsvField::Due) => task.due_date = Some(parse_due_date(value, today).map_err(|e| e.to_string())?),
+                    Some(CsvField::Status) => status = parse_status_value(value)?,
+                    Some(CsvField::Priority) if value.trim().is_empty() => {},
+                    Some(CsvField::Priority) => {
+                        task.priority = Priority::parse(value.trim())
+                            .ok_or_else(|| format!("Unknown priority '{}'", value.trim()))?;
+                    },
+                    Some(CsvField::Tags) => task.tags = parse_tags(value)?,
+                    Some(CsvField::Ignore) | None => {},
+                }
+            }
+            if task.title.is_empty() {
+                return Err("Title is empty".to_string());
+            }
+            Ok(ImportedTask { task, status })
+        })();
+        report.push(line, row);
+    }
+    Ok(report)
+}
+
+// Checklist items look like `- [ ] Title (due 2026-10-20) !high #work`, with the
+// description on the following lines, indented by two spaces. Description lines that
+// are blank, start with `\` or look like checklist items get a `\` in front, so they
+// are read back as part of the description.
+fn export_markdown(tasks: &[Task], now: DateTime<Local>) -> String {
+    let mut out = String::from("# Tasks\n\n");
+    let mut time_by_day: BTreeMap<NaiveDate, Duration> = BTreeMap::new();
+    for task in tasks {
+        let mark = if task.status == TaskStatus::Completed { 'x' } else { ' ' };
+        out.push_str(&format!("- [{}] {}", mark, escape_markdown_title(&task.title.replace('\n', " "))));
+        if let Some(date) = task.due_date {
+            out.push_str(&format!(" (due {})", date.format(DATE_FORMAT)));
+        }
+        if task.priority != Priority::default() {
+            out.push_str(&format!(" !{}", task.priority.to_string().to_lowercase()));
+        }
+        for tag in &task.tags {
+            out.push_str(&format!(" #{}", tag));
+        }
+        if !task.time_entries.is_empty() {
+            out.push_str(&format!(" (tracked {})", format_duration(task.time_spent(now))));
+        }
+        out.push('\n');
+        for line in task.description.split('\n').filter(|_| !task.description.is_empty()) {
+            let escaped = line.trim().is_empty() || line.starts_with('\\') || markdown_item(line.trim_start()).is_some();
+            out.push_str(if escaped { "  \\" } else { "  " });
+            out.push_str(line);
+            out.push('\n');
+        }
+        for (date, duration) in task.time_entries.iter().flat_map(|entry| entry.by_day(now)) {
+            *time_by_day.entry(date).or_insert_with(Duration::zero) += duration;
+        }
+    }
+    // Not checklist items, so importing the file again skips them
+    if !time_by_day.is_empty() {
+        out.push_str("\n## Time tracked\n\n");
+        for (date, duration) in time_by_day {
+            out.push_str(&format!("{}: {}\n", date.format(DATE_FORMAT), format_duration(duration)));
+        }
+    }
+    out
+}
+
+// Escape backslashes, and the `#`, `!` and `(` that start a word, so that the words of
+// a title are not read back as markers
+fn escape_markdown_title(title: &str) -> String {
+    let mut escaped = String::new();
+    let mut word_start = true;
+    for c in title.chars() {
+        if c == '\\' || (word_start && matches!(c, '#' | '!' | '(')) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        word_start = c.is_whitespace();
+    }
+    escaped
+}
+
+// Undo Markdown backslash escapes, which only apply to punctuation
+fn unescape_markdown(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&next) if c == '\\' && next.is_ascii_punctuation() => {
+                unescaped.push(next);
+                chars.next();
+            },
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+// The text after the `[` of a line that starts a checklist item, given without its indentation
+fn markdown_item(line: &str) -> Option<&str> {
+    ["- [", "* [", "+ ["].iter().find_map(|bullet| line.strip_prefix(bullet))
+}
+
+// Parse the text after a checklist box: the title, then optional trailing
+// `(due ...)`, `!priority` and `#tag` markers. Exported `(tracked ...)` times are dropped.
+// Escaped markers are part of the title.
+fn parse_markdown_item(text: &str, status: TaskStatus) -> Result<ImportedTask, String> {
+    let mut task = NewTask::default();
+    let mut rest = text.trim();
+    if let Some((head, _)) = rest.strip_suffix(')').and_then(|rest| rest.rsplit_once(" (tracked ")) {
+        rest = head.trim_end();
+    }
+    while let Some((head, last)) = rest.rsplit_once(' ') {
+        let head = head.trim_end();
+        if let Some(tag) = last.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+            task.tags.insert(0, tag.to_string());
+        } else if let Some(priority) = last.strip_prefix('!').filter(|priority| !priority.is_empty()) {
+            task.priority = Priority::parse(priority).ok_or_else(|| format!("Unknown priority '{}'", priority))?;
+        } else if let (Some(date), Some(title)) = (last.strip_suffix(')'), head.strip_suffix(" (due")) {
+            task.due_date = Some(parse_due_date(date, today()).map_err(|e| e.to_string())?);
+            rest = title.trim_end();
+            continue;
+        } else {
+            break;
+        }
+        rest = head;
+    }
+    if rest.is_empty() {
+        return Err("Checklist item has no title".to_string());
+    }
+    task.title = unescape_markdown(rest);
+    task.tags = parse_tags(&task.tags.join(","))?;
+    Ok(ImportedTask { task, status })
+}
+
+// The text of a line indented under a checklist item, without the indentation and the
+// `\` that export puts in front of some lines. Blank lines end the item.
+fn markdown_description_line(line: &str) -> Option<&str> {
+    let text = ["  ", "\t", " "].iter().find_map(|indent| line.strip_prefix(indent))?;
+    if text.trim().is_empty() {
+        return None;
+    }
+    Some(text.strip_prefix('\\').unwrap_or(text))
+}
+
+// A checklist item being read: its line number, the item or why it is invalid,
+// and its description lines so far
+type MarkdownItem<'a> = (usize, Result<ImportedTask, String>, Vec<&'a str>);
+
+fn push_markdown_item(report: &mut ImportReport, (line, row, description): MarkdownItem) {
+    let row = row.map(|mut imported| {
+        imported.task.description = description.join("\n");
+        imported
+    });
+    report.push(line, row);
+}
+
+// Read `- [ ]`/`- [x]` items (also with `*` or `+` bullets). Other lines, such as headings,
+// are ignored; lines indented under an item form its description.
+fn import_markdown(contents: &str) -> ImportReport {
+    let mut report = ImportReport::default();
+    let mut current: Option<MarkdownItem> = None;
+
+    for (index, line) in contents.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(item) = markdown_item(trimmed) {
+            if let Some(finished) = current.take() {
+                push_markdown_item(&mut report, finished);
+            }
+            let (mark, text) = item.split_once(']').unwrap_or((item, ""));
+            let status = match mark {
+                "" | " " => Ok(TaskStatus::Pending),
+                "x" | "X" => Ok(TaskStatus::Completed),
+                _ => Err(format!("Invalid checkbox in '{}', expected [ ] or [x]", trimmed)),
+            };
+            current = Some((index + 1, status.and_then(|status| parse_markdown_item(text, status)), Vec::new()));
+        } else if let (Some((_, _, description)), Some(text)) = (&mut current, markdown_description_line(line)) {
+            description.push(text);
+        } else if let Some(finished) = current.take() {
+            push_markdown_item(&mut report, finished);
+        }
+    }
+    if let Some(finished) = current {
+        push_markdown_item(&mut report, finished);
+    }
+    report
+}
+
+// Escape text for an iCalendar property value
+fn ical_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+fn ical_unescape(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {},
+        }
+    }
+    out
+}
+
+// Fold a content line so no line is longer than 75 bytes, as RFC 5545 requires
+fn ical_fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+fn export_icalendar(tasks: &[Task], now: DateTime<Local>) -> String {
+    let stamp = now.naive_utc().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Rust Bootcamp//Task Manager//EN".to_string(),
+    ];
+    for task in tasks {
+        lines.push("BEGIN:VTODO".to_string());
+        lines.push(format!("UID:task-{}@task-manager", task.id));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("SUMMARY:{}", ical_escape(&task.title)));
+        if !task.description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", ical_escape(&task.description)));
+        }
+        if let Some(date) = task.due_date {
+            lines.push(format!("DUE;VALUE=DATE:{}", date.format("%Y%m%d")));
+        }
+        let status = match task.status {
+            TaskStatus::Pending => "NEEDS-ACTION",
+            TaskStatus::Completed => "COMPLETED",
+        };
+        lines.push(format!("STATUS:{}", status));
+        // iCalendar priorities run from 1 (highest) to 9 (lowest)
+        let priority = match task.priority {
+            Priority::Urgent => 1,
+            Priority::High => 3,
+            Priority::Medium => 5,
+            Priority::Low => 9,
+        };
+        lines.push(format!("PRIORITY:{}", priority));
+        if !task.tags.is_empty() {
+            let tags: Vec<String> = task.tags.iter().map(|tag| ical_escape(tag)).collect();
+            lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        // iCalendar has no property for time spent, so it goes in an extension property
+        if !task.time_entries.is_empty() {
+            lines.push(format!("X-TIME-SPENT:PT{}M", task.time_spent(now).num_minutes()));
+        }
+        lines.push("END:VTODO".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| ical_fold(line)).collect()
+}
+
+// Build a task from the properties of one VTODO
+fn parse_vtodo(properties: &[(String, String)]) -> Result<ImportedTask, String> {
+    let mut task = NewTask::default();
+    let mut status = TaskStatus::Pending;
+    for (name, value) in properties {
+        // Parameters such as `;VALUE=DATE` are not needed to read the values used here
+        match name.split(';').next().unwrap_or_default().to_uppercase().as_str() {
+            "SUMMARY" => task.title = ical_unescape(value).trim().to_string(),
+            "DESCRIPTION" => task.description = ical_unescape(value),
+            "DUE" => {
+                let date = value.get(..8).unwrap_or(value);
+                task.due_date = Some(NaiveDate::parse_from_str(date, "%Y%m%d")
+                    .map_err(|_| format!("Invalid DUE '{}'", value))?);
+            },
+            "STATUS" if value.eq_ignore_ascii_case("COMPLETED") => status = TaskStatus::Completed,
+            "PRIORITY" => {
+                task.priority = match value.trim().parse::<u8>() {
+                    Ok(1) => Priority::Urgent,
+                    Ok(2..=4) => Priority::High,
+                    Ok(0 | 5) => Priority::Medium,
+                    Ok(6..=9) => Priority::Low,
+                    _ => return Err(format!("Invalid PRIORITY '{}'", value)),
+                };
+            },
+            "CATEGORIES" => {
+                let categories: Vec<String> = value.split(',').map(ical_unescape).collect();
+                task.tags = parse_tags(&categories.join(","))?;
+            },
+            _ => {},
+        }
+    }
+    if task.title.is_empty() {
+        return Err("VTODO has no SUMMARY".to_string());
+    }
+    Ok(ImportedTask { task, status })
+}
+
+fn import_icalendar(contents: &str) -> Result<ImportReport, String> {
+    // Unfold continuation lines, remembering where each logical line starts
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some((_, previous))) => previous.push_str(continuation),
+            _ => lines.push((index + 1, line.to_string())),
+        }
+    }
+    if !lines.iter().any(|(_, line)| line.eq_ignore_ascii_case("BEGIN:VCALENDAR")) {
+        return Err("Not an iCalendar file: BEGIN:VCALENDAR not found".to_string());
+    }
+
+    let mut report = ImportReport::default();
+    let mut todo: Option<(usize, Vec<(String, String)>)> = None;
+    for (line_number, line) in lines {
+        if line.eq_ignore_ascii_case("BEGIN:VTODO") {
+            todo = Some((line_number, Vec::new()));
+        } else if line.eq_ignore_ascii_case("END:VTODO") {
+            if let Some((start, properties)) = todo.take() {
+                report.push(start, parse_vtodo(&properties));
+            }
+        } else if let Some((_, properties)) = &mut todo {
+            match line.split_once(':') {
+                Some((name, value)) => properties.push((name.to_string(), value.to_string())),
+                None => {
+                    let start = todo.take().map(|(start, _)| start).unwrap_or(line_number);
+                    report.push(start, Err(format!("Line {} is not a property: '{}'", line_number, line)));
+                },
+            }
+        }
+    }
+    if let Some((start, _)) = todo {
+        report.push(start, Err("VTODO is missing END:VTODO".to_string()));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_manager::testing::{self, task, FixedClock};
+    use crate::task_manager::reminders::Clock;
+    use proptest::prelude::*;
+
+    fn now() -> DateTime<Local> {
+        FixedClock::at("2026-10-19T09:00:00+00:00").now()
+    }
+
+    fn titles(report: &ImportReport) -> Vec<&str> {
+        report.tasks.iter().map(|imported| imported.task.title.as_str()).collect()
+    }
+
+    // The fields that survive an export and import, as `NewTask` and status
+    fn exported_fields(task: &Task) -> (NewTask, TaskStatus) {
+        let fields = NewTask {
+            title: task.title.clone(),
+            description: task.description.clone(),
+            due_date: task.due_date,
+            priority: task.priority,
+            tags: task.tags.clone(),
+            ..NewTask::default()
+        };
+        (fields, task.status.clone())
+    }
+
+    fn imported_fields(report: ImportReport) -> Vec<(NewTask, TaskStatus)> {
+        report.tasks.into_iter().map(|imported| (imported.task, imported.status)).collect()
+    }
+
+    // Tasks with what the exchange formats can carry: a title without surrounding spaces and
+    // a description without carriage returns, which iCalendar line endings would swallow
+    fn exchangeable_tasks() -> impl Strategy<Value = Vec<Task>> {
+        let fields = ("[^\r\n]{0,20}", "[^\r]{0,30}", prop::option::of(testing::date()), testing::status(), testing::priority(), testing::tags());
+        let task = fields.prop_filter_map("title is blank", |(title, description, due_date, status, priority, tags)| {
+            let title = title.trim().to_string();
+            (!title.is_empty()).then(|| Task { description, due_date, status, priority, tags, ..task(1, &title) })
+        });
+        prop::collection::vec(task, 0..5)
+    }
+
+    #[test]
+    fn csv_columns_are_found_by_header_or_mapping() {
+        let contents = "Summary,Notes,Deadline,Done,Labels\n\
+            \"Buy milk, eggs\",\"Oat\nmilk\",2026-10-20,yes,home\n\
+            ,No title,,,\n\
+            Call mom,,soon,,\n";
+        let report = import_tasks(contents, ExchangeFormat::Csv, &[]).unwrap();
+        assert_eq!(titles(&report), ["Buy milk, eggs"]);
+        let imported = &report.tasks[0];
+        assert_eq!(imported.task.description, "Oat\nmilk");
+        assert_eq!(imported.task.due_date, NaiveDate::from_ymd_opt(2026, 10, 20));
+        assert_eq!(imported.status, TaskStatus::Completed);
+        assert_eq!(imported.task.tags, ["home"]);
+        let failed: Vec<usize> = report.failures.iter().map(|failure| failure.line).collect();
+        assert_eq!(failed, [4, 5]);
+
+        let mapping = [("Notes".to_string(), CsvField::Title)];
+        let report = import_tasks("Name,Notes\nIgnored,Used\n", ExchangeFormat::Csv, &mapping).unwrap();
+        assert_eq!(titles(&report), ["Used"]);
+        assert!(import_tasks("Name,Notes\nx,y\n", ExchangeFormat::Csv, &[("Name".to_string(), CsvField::Ignore)]).is_err());
+    }
+
+    #[test]
+    fn markdown_checklists_round_trip() {
+        let mut done = task(1, "Water plants");
+        done.status = TaskStatus::Completed;
+        done.description = "Kitchen\nand balcony".to_string();
+        let mut urgent = task(2, "File taxes");
+        urgent.due_date = NaiveDate::from_ymd_opt(2026, 10, 31);
+        urgent.priority = Priority::Urgent;
+        urgent.tags = vec!["admin".to_string()];
+        let tasks = [done, urgent];
+
+        let markdown = export_tasks(&tasks, ExchangeFormat::Markdown, now());
+        assert!(markdown.contains("- [ ] File taxes (due 2026-10-31) !urgent #admin\n"));
+        let report = import_tasks(&markdown, ExchangeFormat::Markdown, &[]).unwrap();
+        assert!(report.failures.is_empty());
+        assert_eq!(imported_fields(report), tasks.iter().map(exported_fields).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn markdown_keeps_marker_like_titles_and_description_layout() {
+        let mut tasks = [task(1, "Fix bug #12"), task(2, "Ping Bob !later"), task(3, r"C:\temp (due soon)")];
+        tasks[0].description = "First\n\n  indented\n- [ ] not an item".to_string();
+        tasks[1].description = "\\escaped\n".to_string();
+        tasks[2].due_date = NaiveDate::from_ymd_opt(2026, 10, 31);
+
+        let markdown = export_tasks(&tasks, ExchangeFormat::Markdown, now());
+        assert!(markdown.contains("- [ ] Fix bug \\#12\n  First\n  \\\n    indented\n  \\- [ ] not an item\n"));
+        let report = import_tasks(&markdown, ExchangeFormat::Markdown, &[]).unwrap();
+        assert!(report.failures.is_empty());
+        assert_eq!(imported_fields(report), tasks.iter().map(exported_fields).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn icalendar_lines_are_folded_and_unfolded() {
+        let mut long = task(1, &"x".repeat(100));
+        long.description = "Semicolons; commas, and\nnew lines".to_string();
+        let ical = export_tasks(&[long.clone()], ExchangeFormat::ICalendar, now());
+        assert!(ical.lines().all(|line| line.len() <= 75));
+        assert!(ical.contains("DTSTAMP:20261019T090000Z"));
+        let report = import_tasks(&ical, ExchangeFormat::ICalendar, &[]).unwrap();
+        assert_eq!(imported_fields(report), [exported_fields(&long)]);
+        assert!(import_tasks("BEGIN:VTODO\n", ExchangeFormat::ICalendar, &[]).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn csv_exports_import_back(tasks in exchangeable_tasks()) {
+            let csv = export_tasks(&tasks, ExchangeFormat::Csv, now());
+            let report = import_tasks(&csv, ExchangeFormat::Csv, &[]).unwrap();
+            prop_assert!(report.failures.is_empty());
+            prop_assert_eq!(imported_fields(report), tasks.iter().map(exported_fields).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn markdown_exports_import_back(tasks in exchangeable_tasks()) {
+            let markdown = export_tasks(&tasks, ExchangeFormat::Markdown, now());
+            let report = import_tasks(&markdown, ExchangeFormat::Markdown, &[]).unwrap();
+            prop_assert!(report.failures.is_empty());
+            prop_assert_eq!(imported_fields(report), tasks.iter().map(exported_fields).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn icalendar_exports_import_back(tasks in exchangeable_tasks()) {
+            let ical = export_tasks(&tasks, ExchangeFormat::ICalendar, now());
+            let report = import_tasks(&ical, ExchangeFormat::ICalendar, &[]).unwrap();
+            prop_assert!(report.failures.is_empty());
+            prop_assert_eq!(imported_fields(report), tasks.iter().map(exported_fields).collect::<Vec<_>>());
+        }
+    }
+}
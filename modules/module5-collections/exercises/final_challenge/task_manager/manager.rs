@@ -0,0 +1,1474 @@
+// The task manager: tasks of all projects, with undo and autosave
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fs;
+use std::io;
+use std::path::Path;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate};
+use serde::Serialize;
+
+use super::error::TaskError;
+use super::exchange::ImportedTask;
+use super::query::TaskFilter;
+use super::reminders::{collect_reminders, Reminder, Reminders};
+use super::search::{search_tasks, SearchHit};
+use super::storage::{open_storage, LoadReport, Storage, StorageFormat, TaskFile};
+use super::task::{NewTask, Recurrence, Task, TaskStatus, TaskUpdate, TimeEntry};
+
+// Project that always exists; files written before projects existed hold only its tasks
+pub const DEFAULT_PROJECT: &str = "default";
+
+// Project names are single words, so they can be typed without quotes
+fn validate_project_name(name: &str) -> Result<(), TaskError> {
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err(TaskError::InvalidProjectName(name.to_string()));
+    }
+    Ok(())
+}
+
+// Number of changes kept for undo
+const UNDO_DEPTH: usize = 50;
+
+// A TaskManager mutation, recorded with enough state to undo and redo it
+#[derive(Debug, Clone)]
+enum Change {
+    Added(Task),
+    // `action` names the operation for messages, e.g. "complete" or "edit"
+    Updated { action: &'static str, before: Task, after: Task },
+    Deleted { task: Task, position: usize },
+    Imported(Vec<Task>),
+    // `before` was taken out of the current project at `position`; `after` went to `project`
+    Moved { before: Task, after: Task, position: usize, project: String },
+    // Several changes made by one command, undone and redone together.
+    // The first change describes the batch.
+    Batch(Vec<Change>),
+}
+
+impl Change {
+    fn describe(&self) -> String {
+        match self {
+            Change::Added(task) => format!("add of task #{}", task.id),
+            Change::Updated { action, after, .. } => format!("{} of task #{}", action, after.id),
+            Change::Deleted { task, .. } => format!("delete of task #{}", task.id),
+            Change::Imported(tasks) => format!("import of {} tasks", tasks.len()),
+            Change::Moved { after, project, .. } => format!("move of task #{} to '{}'", after.id, project),
+            Change::Batch(changes) => changes.first().map(Change::describe).unwrap_or_default(),
+        }
+    }
+}
+
+// Summary of the task list for the `stats` command
+#[derive(Debug, Default)]
+pub struct TaskStats {
+    pub total: usize,
+    pub pending: usize,
+    pub completed: usize,
+    pub overdue: usize,
+    pub by_date: BTreeMap<NaiveDate, usize>,
+    // Keyed by the Monday starting each week
+    pub by_week: BTreeMap<NaiveDate, usize>,
+    pub average_time_to_complete: Option<Duration>,
+    // Number of completed tasks the average is based on
+    pub timed_completions: usize,
+    // Tasks with tracked time, most time first
+    pub time_by_task: Vec<TrackedTime>,
+    pub time_by_day: BTreeMap<NaiveDate, Duration>,
+}
+
+// Time tracked on one task
+#[derive(Debug)]
+pub struct TrackedTime {
+    pub id: u32,
+    pub title: String,
+    pub total: Duration,
+    pub running: bool,
+}
+
+impl TaskStats {
+    // Share of tasks that are completed, as a percentage
+    pub fn completion_rate(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.completed as f64 * 100.0 / self.total as f64)
+    }
+}
+
+// A date on which a task falls due, for listings that expand recurring tasks
+#[derive(Debug, Serialize)]
+pub struct Occurrence<'a> {
+    pub date: NaiveDate,
+    pub task: &'a Task,
+}
+
+// Where the current tasks are stored: the file they were loaded from or autosaved to
+struct Source {
+    // `None` for a storage given without a file name
+    filename: Option<String>,
+    storage: Box<dyn Storage>,
+}
+
+impl Source {
+    fn is_file(&self, filename: &str) -> bool {
+        self.filename.as_deref().is_some_and(|own| {
+            own == filename
+                || matches!((fs::canonicalize(own), fs::canonicalize(filename)), (Ok(own), Ok(other)) if own == other)
+        })
+    }
+}
+
+// TaskManager to handle operations on tasks
+pub struct TaskManager {
+    // Tasks of the current project
+    tasks: Vec<Task>,
+    // Task IDs are unique across all projects
+    next_id: u32,
+    project: String,
+    // Tasks of every project except the current one
+    other_projects: BTreeMap<String, Vec<Task>>,
+    undo_stack: VecDeque<Change>,
+    redo_stack: Vec<Change>,
+    source: Option<Source>,
+    // When set, tasks are saved to `source` after every change
+    autosave: bool,
+    pub reminders: Reminders,
+}
+
+impl TaskManager {
+    // Create a new TaskManager
+    pub fn new() -> TaskManager {
+        TaskManager {
+            tasks: Vec::new(),
+            next_id: 1,
+            project: DEFAULT_PROJECT.to_string(),
+            other_projects: BTreeMap::new(),
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            source: None,
+            autosave: false,
+            reminders: Reminders::default(),
+        }
+    }
+    
+    // Tasks of the current project, in list order
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+    
+    // Name of the current project
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+    
+    // Remember a new change for undo; it invalidates anything that could be redone
+    fn record(&mut self, change: Change) {
+        if self.undo_stack.len() == UNDO_DEPTH {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(change);
+        self.redo_stack.clear();
+    }
+    
+    // Forget all recorded changes, e.g. after loading a different set of tasks
+    fn clear_history(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+    
+    // Revert the most recent change, returning a description of it
+    pub fn undo(&mut self) -> Result<String, TaskError> {
+        let change = self.undo_stack.pop_back().ok_or(TaskError::NothingToUndo)?;
+        self.revert(&change);
+        let description = change.describe();
+        self.redo_stack.push(change);
+        Ok(description)
+    }
+    
+    // Re-apply the most recently undone change, returning a description of it
+    pub fn redo(&mut self) -> Result<String, TaskError> {
+        let change = self.redo_stack.pop().ok_or(TaskError::NothingToRedo)?;
+        self.apply(&change);
+        let description = change.describe();
+        self.undo_stack.push_back(change);
+        Ok(description)
+    }
+    
+    fn apply(&mut self, change: &Change) {
+        match change {
+            Change::Added(task) => self.tasks.push(task.clone()),
+            Change::Updated { after, .. } => self.replace_task(after.clone()),
+            Change::Deleted { task, .. } => self.remove_by_id(task.id),
+            Change::Imported(tasks) => self.tasks.extend(tasks.iter().cloned()),
+            Change::Moved { before, after, project, .. } => {
+                self.remove_by_id(before.id);
+                self.other_projects.entry(project.clone()).or_default().push(after.clone());
+            },
+            Change::Batch(changes) => changes.iter().for_each(|change| self.apply(change)),
+        }
+    }
+    
+    fn revert(&mut self, change: &Change) {
+        match change {
+            Change::Added(task) => self.remove_by_id(task.id),
+            Change::Updated { before, .. } => self.replace_task(before.clone()),
+            Change::Deleted { task, position } => {
+                let position = (*position).min(self.tasks.len());
+                self.tasks.insert(position, task.clone());
+            },
+            Change::Imported(tasks) => tasks.iter().for_each(|task| self.remove_by_id(task.id)),
+            Change::Moved { before, after, position, project } => {
+                if let Some(tasks) = self.other_projects.get_mut(project) {
+                    tasks.retain(|task| task.id != after.id);
+                }
+                let position = (*position).min(self.tasks.len());
+                self.tasks.insert(position, before.clone());
+            },
+            Change::Batch(changes) => changes.iter().rev().for_each(|change| self.revert(change)),
+        }
+    }
+    
+    fn remove_by_id(&mut self, id: u32) {
+        self.tasks.retain(|task| task.id != id);
+    }
+    
+    fn replace_task(&mut self, task: Task) {
+        if let Some(existing) = self.find_task_mut(task.id) {
+            *existing = task;
+        }
+    }
+    
+    // Add a new task to the task manager
+    pub fn add_task(&mut self, new_task: NewTask, now: DateTime<Local>) -> Result<&Task, TaskError> {
+        if new_task.title.trim().is_empty() {
+            return Err(TaskError::EmptyTitle);
+        }
+        self.check_links(self.next_id, new_task.parent, &new_task.depends_on)?;
+        let task = Task {
+            id: self.take_next_id()?,
+            title: new_task.title,
+            description: new_task.description,
+            due_date: new_task.due_date,
+            status: TaskStatus::Pending,
+            priority: new_task.priority,
+            tags: new_task.tags,
+            recurrence: new_task.recurrence,
+            parent: new_task.parent,
+            depends_on: new_task.depends_on,
+            created_at: Some(now),
+            completed_at: None,
+            time_entries: Vec::new(),
+            snoozed_until: None,
+        };
+        
+        self.record(Change::Added(task.clone()));
+        self.tasks.push(task);
+        Ok(self.tasks.last().unwrap())
+    }
+    
+    // Add imported tasks with new IDs, returning how many were added.
+    // The import is undone as a whole, and nothing is added unless every task gets an ID.
+    pub fn import_tasks(&mut self, imported: Vec<ImportedTask>, now: DateTime<Local>) -> Result<usize, TaskError> {
+        u32::try_from(imported.len()).ok()
+            .and_then(|count| self.next_id.checked_add(count))
+            .ok_or(TaskError::IdOutOfRange(u32::MAX))?;
+        let mut tasks = Vec::new();
+        for ImportedTask { task, status } in imported {
+            tasks.push(Task {
+                id: self.take_next_id()?,
+                title: task.title,
+                description: task.description,
+                due_date: task.due_date,
+                status,
+                priority: task.priority,
+                tags: task.tags,
+                recurrence: None,
+                parent: None,
+                depends_on: Vec::new(),
+                created_at: Some(now),
+                completed_at: None,
+                time_entries: Vec::new(),
+                snoozed_until: None,
+            });
+        }
+        
+        let count = tasks.len();
+        if count > 0 {
+            self.tasks.extend(tasks.iter().cloned());
+            self.record(Change::Imported(tasks));
+        }
+        Ok(count)
+    }
+    
+    // Take the next task ID, failing rather than overflowing once none are left
+    fn take_next_id(&mut self) -> Result<u32, TaskError> {
+        let id = self.next_id;
+        self.next_id = id.checked_add(1).ok_or(TaskError::IdOutOfRange(id))?;
+        Ok(id)
+    }
+    
+    // Check that task `id` may have the given parent and dependencies: they must exist,
+    // and following parents or dependencies from them must never lead back to `id`
+    fn check_links(&self, id: u32, parent: Option<u32>, depends_on: &[u32]) -> Result<(), TaskError> {
+        if let Some(parent) = parent {
+            if parent == id {
+                return Err(TaskError::OwnParent(id));
+            }
+            let mut ancestor = Some(self.find_task(parent).ok_or(TaskError::ParentNotFound(parent))?);
+            let mut seen = HashSet::new();
+            while let Some(task) = ancestor.filter(|task| seen.insert(task.id)) {
+                if task.id == id {
+                    return Err(TaskError::SubtaskLoop { id, parent });
+                }
+                ancestor = task.parent.and_then(|parent| self.find_task(parent));
+            }
+        }
+        
+        let mut visited = HashSet::new();
+        for &dependency in depends_on {
+            if dependency == id {
+                return Err(TaskError::OwnDependency(id));
+            }
+            if self.find_task(dependency).is_none() {
+                return Err(TaskError::NotFound(dependency));
+            }
+            if let Some(path) = self.dependency_path(dependency, id, &mut visited) {
+                return Err(TaskError::DependencyCycle(std::iter::once(id).chain(path).collect()));
+            }
+        }
+        Ok(())
+    }
+    
+    // The chain of dependencies leading from task `from` to task `to`, if there is one.
+    // Tasks in `visited` have already been searched.
+    fn dependency_path(&self, from: u32, to: u32, visited: &mut HashSet<u32>) -> Option<Vec<u32>> {
+        if from == to {
+            return Some(vec![to]);
+        }
+        if !visited.insert(from) {
+            return None;
+        }
+        for &next in &self.find_task(from)?.depends_on {
+            if let Some(mut path) = self.dependency_path(next, to, visited) {
+                path.insert(0, from);
+                return Some(path);
+            }
+        }
+        None
+    }
+    
+    // The dependencies that are still pending, i.e. what a task is waiting on
+    pub fn pending_dependencies(&self, depends_on: &[u32]) -> Vec<u32> {
+        depends_on.iter()
+            .copied()
+            .filter(|&id| self.find_task(id).is_some_and(|dependency| dependency.status == TaskStatus::Pending))
+            .collect()
+    }
+    
+    // Direct subtasks of a task, in list order
+    pub fn subtasks(&self, id: u32) -> Vec<&Task> {
+        self.tasks.iter()
+            .filter(|task| task.parent == Some(id))
+            .collect()
+    }
+    
+    // Find a task by ID
+    pub fn find_task(&self, id: u32) -> Option<&Task> {
+        self.tasks.iter().find(|task| task.id == id)
+    }
+    
+    // Find a task by ID (mutable version)
+    pub fn find_task_mut(&mut self, id: u32) -> Option<&mut Task> {
+        self.tasks.iter_mut().find(|task| task.id == id)
+    }
+    
+    // Mark a task as complete. Completing a recurring task also adds its next occurrence,
+    // which is returned. The completed task stops repeating, so it is only scheduled once.
+    // Tasks with pending dependencies cannot be completed.
+    pub fn complete_task(&mut self, id: u32, now: DateTime<Local>) -> Result<Option<&Task>, TaskError> {
+        let task = self.find_task(id).ok_or(TaskError::NotFound(id))?;
+        let blockers = self.pending_dependencies(&task.depends_on);
+        if !blockers.is_empty() {
+            return Err(TaskError::Blocked { id, blockers });
+        }
+        
+        let task = self.find_task_mut(id).ok_or(TaskError::NotFound(id))?;
+        if task.status == TaskStatus::Completed {
+            return Ok(None);
+        }
+        
+        let before = task.clone();
+        task.status = TaskStatus::Completed;
+        task.completed_at = Some(now);
+        task.stop_timer(now);
+        let recurrence = task.recurrence.take();
+        let after = task.clone();
+        
+        let change = Change::Updated { action: "complete", before, after };
+        self.record_completion(change, recurrence, now)
+    }
+    
+    // Record the completion of a task, and for a recurring task add and return its next
+    // occurrence. `change` holds the completed task, whose recurrence has been taken; it
+    // is reverted if there is no ID left for the next occurrence.
+    fn record_completion(&mut self, change: Change, recurrence: Option<Recurrence>, now: DateTime<Local>) -> Result<Option<&Task>, TaskError> {
+        let (Some(recurrence), Change::Updated { before, after: completed, .. }) = (recurrence, &change) else {
+            self.record(change);
+            return Ok(None);
+        };
+        let id = match self.take_next_id() {
+            Ok(id) => id,
+            Err(error) => {
+                self.replace_task(before.clone());
+                return Err(error);
+            },
+        };
+        
+        // The next occurrence follows the current due date, skipping any that have
+        // already passed so the new task does not start out overdue
+        let today = now.date_naive();
+        let mut due = recurrence.next_after(completed.due_date.unwrap_or(today));
+        while let Some(date) = due.filter(|date| *date < today) {
+            due = recurrence.next_after(date);
+        }
+        let next = Task {
+            id,
+            due_date: due,
+            status: TaskStatus::Pending,
+            recurrence: Some(recurrence),
+            created_at: Some(now),
+            completed_at: None,
+            time_entries: Vec::new(),
+            snoozed_until: None,
+            ..completed.clone()
+        };
+        self.record(Change::Batch(vec![change, Change::Added(next.clone())]));
+        self.tasks.push(next);
+        Ok(self.tasks.last())
+    }
+    
+    // Tasks whose timer is running, in every project
+    pub fn running_tasks(&self) -> impl Iterator<Item = &Task> {
+        self.tasks.iter()
+            .chain(self.other_projects.values().flatten())
+            .filter(|task| task.running_since().is_some())
+    }
+    
+    // Start timing work on a task. Only one task can run at a time unless `parallel` is set.
+    pub fn start_timer(&mut self, id: u32, now: DateTime<Local>, parallel: bool) -> Result<(), TaskError> {
+        let task = self.find_task(id).ok_or(TaskError::NotFound(id))?;
+        if task.running_since().is_some() {
+            return Err(TaskError::AlreadyRunning(id));
+        }
+        if task.status == TaskStatus::Completed {
+            return Err(TaskError::Completed(id));
+        }
+        let running: Vec<u32> = self.running_tasks().map(|task| task.id).collect();
+        if !parallel && !running.is_empty() {
+            return Err(TaskError::TimersRunning(running));
+        }
+        
+        let task = self.find_task_mut(id).ok_or(TaskError::NotFound(id))?;
+        let before = task.clone();
+        task.time_entries.push(TimeEntry { start: now, end: None });
+        let after = task.clone();
+        self.record(Change::Updated { action: "start", before, after });
+        Ok(())
+    }
+    
+    // Stop a task's running timer, returning how long it ran
+    pub fn stop_timer(&mut self, id: u32, now: DateTime<Local>) -> Result<Duration, TaskError> {
+        let task = self.find_task_mut(id).ok_or(TaskError::NotFound(id))?;
+        let before = task.clone();
+        let duration = task.stop_timer(now).ok_or(TaskError::NotRunning(id))?;
+        let after = task.clone();
+        self.record(Change::Updated { action: "stop", before, after });
+        Ok(duration)
+    }
+    
+    // Apply field-level changes to a task, returning the names of the fields that changed.
+    // Nothing is modified if any of the new values is invalid. Completing a recurring task
+    // adds its next occurrence, which is returned, as `complete_task` does.
+    pub fn update_task(&mut self, id: u32, update: TaskUpdate, now: DateTime<Local>) -> Result<(Vec<&'static str>, Option<&Task>), TaskError> {
+        if update.title.as_ref().is_some_and(|title| title.trim().is_empty()) {
+            return Err(TaskError::EmptyTitle);
+        }
+        
+        let task = self.find_task(id).ok_or(TaskError::NotFound(id))?;
+        let parent = update.parent.unwrap_or(task.parent);
+        let depends_on = update.depends_on.as_ref().unwrap_or(&task.depends_on);
+        self.check_links(id, parent, depends_on)?;
+        if update.status == Some(TaskStatus::Completed) && task.status != TaskStatus::Completed {
+            let blockers = self.pending_dependencies(depends_on);
+            if !blockers.is_empty() {
+                return Err(TaskError::Blocked { id, blockers });
+            }
+        }
+        
+        let task = self.find_task_mut(id).ok_or(TaskError::NotFound(id))?;
+        let before = task.clone();
+        let mut changed = Vec::new();
+        
+        if let Some(title) = update.title {
+            if title != task.title {
+                task.title = title;
+                changed.push("title");
+            }
+        }
+        if let Some(description) = update.description {
+            if description != task.description {
+                task.description = description;
+                changed.push("description");
+            }
+        }
+        if let Some(due_date) = update.due_date {
+            if due_date != task.due_date {
+                task.due_date = due_date;
+                changed.push("due date");
+            }
+        }
+        if let Some(status) = update.status {
+            if status != task.status {
+                task.completed_at = (status == TaskStatus::Completed).then_some(now);
+                if status == TaskStatus::Completed {
+                    task.stop_timer(now);
+                }
+                task.status = status;
+                changed.push("status");
+            }
+        }
+        if let Some(priority) = update.priority {
+            if priority != task.priority {
+                task.priority = priority;
+                changed.push("priority");
+            }
+        }
+        if let Some(tags) = update.tags {
+            if tags != task.tags {
+                task.tags = tags;
+                changed.push("tags");
+            }
+        }
+        if let Some(recurrence) = update.recurrence {
+            if recurrence != task.recurrence {
+                task.recurrence = recurrence;
+                changed.push("recurrence");
+            }
+        }
+        if let Some(parent) = update.parent {
+            if parent != task.parent {
+                task.parent = parent;
+                changed.push("parent");
+            }
+        }
+        if let Some(depends_on) = update.depends_on {
+            if depends_on != task.depends_on {
+                task.depends_on = depends_on;
+                changed.push("dependencies");
+            }
+        }
+        
+        if changed.is_empty() {
+            return Ok((changed, None));
+        }
+        let completed = before.status != TaskStatus::Completed && task.status == TaskStatus::Completed;
+        let recurrence = if completed { task.recurrence.take() } else { None };
+        let after = task.clone();
+        let next = self.record_completion(Change::Updated { action: "edit", before, after }, recurrence, now)?;
+        Ok((changed, next))
+    }
+    
+    // Delete a task. Its subtasks move up to its own parent, and tasks that depended on it
+    // no longer do; undo restores them together with the task.
+    pub fn delete_task(&mut self, id: u32) -> Result<(), TaskError> {
+        let position = self.tasks.iter().position(|task| task.id == id).ok_or(TaskError::NotFound(id))?;
+        let task = self.tasks.remove(position);
+        let mut changes = self.unlink(&task);
+        
+        let deleted = Change::Deleted { task, position };
+        if changes.is_empty() {
+            self.record(deleted);
+        } else {
+            changes.insert(0, deleted);
+            self.record(Change::Batch(changes));
+        }
+        Ok(())
+    }
+    
+    // Remove references to a task that is leaving the current project: its subtasks move up
+    // to its parent and other tasks stop depending on it. Returns the resulting changes.
+    fn unlink(&mut self, removed: &Task) -> Vec<Change> {
+        let id = removed.id;
+        let mut changes = Vec::new();
+        for other in self.tasks.iter_mut().filter(|other| other.parent == Some(id) || other.depends_on.contains(&id)) {
+            let before = other.clone();
+            if other.parent == Some(id) {
+                other.parent = removed.parent;
+            }
+            other.depends_on.retain(|&dependency| dependency != id);
+            changes.push(Change::Updated { action: "edit", before, after: other.clone() });
+        }
+        changes
+    }
+    
+    // Move a task to another project. Links between projects are not kept, so the task
+    // loses its parent and dependencies, and tasks here stop referring to it.
+    pub fn move_task(&mut self, id: u32, project: &str) -> Result<(), TaskError> {
+        if project == self.project {
+            return Err(TaskError::AlreadyInProject { id, project: project.to_string() });
+        }
+        if !self.other_projects.contains_key(project) {
+            return Err(TaskError::ProjectNotFound(project.to_string()));
+        }
+        let position = self.tasks.iter().position(|task| task.id == id).ok_or(TaskError::NotFound(id))?;
+        let before = self.tasks.remove(position);
+        let after = Task { parent: None, depends_on: Vec::new(), ..before.clone() };
+        let mut changes = self.unlink(&before);
+        self.other_projects.get_mut(project).unwrap().push(after.clone());
+        
+        let moved = Change::Moved { before, after, position, project: project.to_string() };
+        if changes.is_empty() {
+            self.record(moved);
+        } else {
+            changes.insert(0, moved);
+            self.record(Change::Batch(changes));
+        }
+        Ok(())
+    }
+    
+    // Names of all projects with their tasks, in name order
+    pub fn projects(&self) -> Vec<(&str, &[Task])> {
+        let mut projects: Vec<(&str, &[Task])> = self.other_projects.iter()
+            .map(|(name, tasks)| (name.as_str(), tasks.as_slice()))
+            .collect();
+        projects.push((&self.project, &self.tasks));
+        projects.sort_by_key(|&(name, _)| name);
+        projects
+    }
+    
+    fn project_exists(&self, name: &str) -> bool {
+        name == self.project || self.other_projects.contains_key(name)
+    }
+    
+    pub fn create_project(&mut self, name: &str) -> Result<(), TaskError> {
+        validate_project_name(name)?;
+        if self.project_exists(name) {
+            return Err(TaskError::ProjectExists(name.to_string()));
+        }
+        self.other_projects.insert(name.to_string(), Vec::new());
+        Ok(())
+    }
+    
+    // Make another project current. Undo history is cleared, as it refers to the
+    // tasks of the project being left.
+    pub fn switch_project(&mut self, name: &str) -> Result<(), TaskError> {
+        if name == self.project {
+            return Ok(());
+        }
+        let tasks = self.other_projects.remove(name).ok_or_else(|| TaskError::ProjectNotFound(name.to_string()))?;
+        let previous = std::mem::replace(&mut self.project, name.to_string());
+        let previous_tasks = std::mem::replace(&mut self.tasks, tasks);
+        self.other_projects.insert(previous, previous_tasks);
+        self.clear_history();
+        Ok(())
+    }
+    
+    pub fn rename_project(&mut self, name: &str, new_name: &str) -> Result<(), TaskError> {
+        if name == DEFAULT_PROJECT {
+            return Err(TaskError::CannotRenameDefault);
+        }
+        validate_project_name(new_name)?;
+        if !self.project_exists(name) {
+            return Err(TaskError::ProjectNotFound(name.to_string()));
+        }
+        if self.project_exists(new_name) {
+            return Err(TaskError::ProjectExists(new_name.to_string()));
+        }
+        if name == self.project {
+            self.project = new_name.to_string();
+        } else {
+            let tasks = self.other_projects.remove(name).unwrap_or_default();
+            self.other_projects.insert(new_name.to_string(), tasks);
+        }
+        // Recorded moves refer to projects by name
+        self.clear_history();
+        Ok(())
+    }
+    
+    // Delete a project that is not current. A project that still has tasks is only
+    // deleted with `force`, and its tasks are deleted with it.
+    pub fn delete_project(&mut self, name: &str, force: bool) -> Result<usize, TaskError> {
+        if name == DEFAULT_PROJECT {
+            return Err(TaskError::CannotDeleteDefault);
+        }
+        if name == self.project {
+            return Err(TaskError::CurrentProject(name.to_string()));
+        }
+        let tasks = self.other_projects.get(name).ok_or_else(|| TaskError::ProjectNotFound(name.to_string()))?;
+        if !tasks.is_empty() && !force {
+            return Err(TaskError::ProjectNotEmpty { name: name.to_string(), tasks: tasks.len() });
+        }
+        let count = tasks.len();
+        self.other_projects.remove(name);
+        self.clear_history();
+        Ok(count)
+    }
+    
+    // Tasks matching every condition of the filter
+    pub fn filter(&self, filter: &TaskFilter) -> Vec<&Task> {
+        self.tasks.iter()
+            .filter(|task| filter.matches(task))
+            .collect()
+    }
+    
+    // Filter tasks by status
+    pub fn filter_by_status(&self, status: &TaskStatus) -> Vec<&Task> {
+        self.tasks.iter()
+            .filter(|task| task.status == *status)
+            .collect()
+    }
+    
+    // Filter tasks due on the given date
+    pub fn filter_by_due_date(&self, due_date: NaiveDate) -> Vec<&Task> {
+        self.tasks.iter()
+            .filter(|task| task.due_date == Some(due_date))
+            .collect()
+    }
+    
+    // Filter tasks due strictly before the given date
+    pub fn filter_due_before(&self, date: NaiveDate) -> Vec<&Task> {
+        self.tasks.iter()
+            .filter(|task| task.due_date.is_some_and(|due| due < date))
+            .collect()
+    }
+    
+    // Pending tasks whose due date has passed
+    pub fn overdue_tasks(&self, today: NaiveDate) -> Vec<&Task> {
+        self.tasks.iter()
+            .filter(|task| task.is_overdue(today))
+            .collect()
+    }
+    
+    // Current reminders for the tasks of the current project
+    pub fn reminders(&self, now: DateTime<Local>) -> Vec<Reminder<'_>> {
+        collect_reminders(&self.tasks, self.reminders.lead_time, now)
+    }
+    
+    // Reminders that have not been shown yet, which are marked as shown. Empty while reminders are off.
+    pub fn new_reminders(&mut self, now: DateTime<Local>) -> Vec<Reminder<'_>> {
+        if !self.reminders.enabled {
+            return Vec::new();
+        }
+        let shown = &mut self.reminders.shown;
+        let mut reminders = collect_reminders(&self.tasks, self.reminders.lead_time, now);
+        reminders.retain(|reminder| shown.insert((reminder.task.id, reminder.kind, reminder.due, reminder.task.snoozed_until)));
+        reminders
+    }
+    
+    // Hold back reminders about a task until `until`, or show them again with `None`
+    pub fn snooze(&mut self, id: u32, until: Option<DateTime<Local>>) -> Result<(), TaskError> {
+        let task = self.find_task_mut(id).ok_or(TaskError::NotFound(id))?;
+        if until.is_some() && task.status == TaskStatus::Completed {
+            return Err(TaskError::Completed(id));
+        }
+        let before = task.clone();
+        task.snoozed_until = until;
+        let after = task.clone();
+        self.record(Change::Updated { action: "snooze", before, after });
+        Ok(())
+    }
+    
+    // Due dates of pending tasks from `today` to `until` inclusive, in date order.
+    // Recurring tasks appear once for each of their occurrences in that range.
+    pub fn upcoming(&self, today: NaiveDate, until: NaiveDate) -> Vec<Occurrence<'_>> {
+        let mut occurrences = Vec::new();
+        for task in self.tasks.iter().filter(|task| task.status == TaskStatus::Pending) {
+            let mut due = task.due_date;
+            while let Some(date) = due.filter(|date| *date <= until) {
+                if date >= today {
+                    occurrences.push(Occurrence { date, task });
+                }
+                due = task.recurrence.as_ref().and_then(|recurrence| recurrence.next_after(date));
+            }
+        }
+        occurrences.sort_by_key(|occurrence| (occurrence.date, occurrence.task.id));
+        occurrences
+    }
+    
+    // Tasks of the current project matching a search query, best matches first
+    pub fn search(&self, query: &str) -> Vec<SearchHit<'_>> {
+        search_tasks(&self.tasks, query)
+    }
+    
+    // All tasks and projects, in the shape they are stored in
+    pub fn snapshot(&self) -> TaskFile<&[Task]> {
+        let mut projects: BTreeMap<String, &[Task]> = self.projects().into_iter()
+            .map(|(name, tasks)| (name.to_string(), tasks))
+            .collect();
+        let tasks = projects.remove(DEFAULT_PROJECT).unwrap_or_default();
+        // Keep files without projects readable by versions that did not have them
+        let project = (self.project != DEFAULT_PROJECT).then(|| self.project.clone());
+        TaskFile { next_id: self.next_id, tasks, projects, project }
+    }
+    
+    // Whether there is nothing that loading a file would lose: no tasks and no named projects
+    fn is_blank(&self) -> bool {
+        self.projects().iter().all(|(name, tasks)| *name == DEFAULT_PROJECT && tasks.is_empty())
+    }
+    
+    // Replace all tasks and projects with loaded ones. The tasks no longer come from
+    // the file they were autosaved to, so autosave is turned off.
+    pub fn restore(&mut self, file: TaskFile<Vec<Task>>) {
+        self.next_id = file.next_id;
+        self.other_projects = file.projects;
+        self.other_projects.insert(DEFAULT_PROJECT.to_string(), file.tasks);
+        // Fall back to the default project if the file names one it does not have
+        let project = file.project.filter(|name| self.other_projects.contains_key(name));
+        self.project = project.unwrap_or_else(|| DEFAULT_PROJECT.to_string());
+        self.tasks = self.other_projects.remove(&self.project).unwrap_or_default();
+        // Changes made before loading no longer apply to these tasks
+        self.clear_history();
+        self.source = None;
+        self.autosave = false;
+    }
+    
+    // Save tasks to a file in the given format
+    pub fn save_to_file(&self, filename: &str, format: StorageFormat) -> io::Result<()> {
+        open_storage(filename, format)?.save(&self.snapshot())
+    }
+    
+    // Save to the autosave storage, if autosave is enabled
+    pub fn autosave(&self) -> io::Result<()> {
+        match &self.source {
+            Some(source) if self.autosave => source.storage.save(&self.snapshot()),
+            _ => Ok(()),
+        }
+    }
+    
+    // Whether changes are saved after they are made
+    pub fn autosaving(&self) -> bool {
+        self.autosave
+    }
+    
+    // Bind autosave to a file, saving the current tasks to it. The file the tasks were
+    // loaded from is always accepted. Another existing file is loaded instead when there are
+    // no tasks yet, and is never overwritten by a different set of tasks. Returns the report
+    // of that load.
+    pub fn enable_autosave(&mut self, filename: &str) -> io::Result<Option<LoadReport>> {
+        if let Some(source) = self.source.as_ref().filter(|source| source.is_file(filename)) {
+            source.storage.save(&self.snapshot())?;
+            self.autosave = true;
+            return Ok(None);
+        }
+        
+        let storage = open_storage(filename, StorageFormat::of_file(filename))?;
+        let report = match storage.load()? {
+            Some(_) if !self.is_blank() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("'{}' already has tasks; load it first or choose another file", filename),
+                ));
+            },
+            Some((file, report)) => {
+                self.restore(file);
+                Some(report)
+            },
+            None => {
+                storage.save(&self.snapshot())?;
+                None
+            },
+        };
+        self.source = Some(Source { filename: Some(filename.to_string()), storage });
+        self.autosave = true;
+        Ok(report)
+    }
+    
+    // Save to `storage` after every change from now on, or stop autosaving with `None`
+    pub fn set_storage(&mut self, storage: Option<Box<dyn Storage>>) {
+        self.autosave = storage.is_some();
+        if let Some(storage) = storage {
+            self.source = Some(Source { filename: None, storage });
+        }
+    }
+    
+    // Load tasks from a file if it exists, and save every change back to it
+    pub fn open_file(&mut self, filename: &str, strict: bool) -> io::Result<Option<LoadReport>> {
+        let storage = open_storage(filename, StorageFormat::of_file(filename))?;
+        let report = self.load_from(storage.as_ref(), strict)?;
+        self.source = Some(Source { filename: Some(filename.to_string()), storage });
+        self.autosave = true;
+        Ok(report)
+    }
+    
+    // Load tasks from a storage, replacing the current ones. Returns `None` if it holds no
+    // tasks yet. Tasks that cannot be loaded are skipped and listed in the report; in strict
+    // mode they fail the load instead, leaving the current tasks untouched.
+    pub fn load_from(&mut self, storage: &dyn Storage, strict: bool) -> io::Result<Option<LoadReport>> {
+        let Some((file, report)) = storage.load()? else {
+            return Ok(None);
+        };
+        if strict && !report.is_clean() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, report.warnings().join("; ")));
+        }
+        self.restore(file);
+        Ok(Some(report))
+    }
+    
+    // Load tasks from a file, detecting SQLite, JSON or the legacy format from its contents
+    pub fn load_from_file(&mut self, filename: &str, strict: bool) -> io::Result<LoadReport> {
+        if !Path::new(filename).exists() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "File not found"));
+        }
+        let storage = open_storage(filename, StorageFormat::of_file(filename))?;
+        let autosave = self.autosave;
+        let report = self.load_from(storage.as_ref(), strict)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "The database holds no tasks"))?;
+        // Changes to the loaded tasks belong in the loaded file, so autosave moves there
+        self.source = Some(Source { filename: Some(filename.to_string()), storage });
+        self.autosave = autosave;
+        Ok(report)
+    }
+    
+    // Generate statistics about tasks, counting running timers up to `now`
+    pub fn generate_stats(&self, now: DateTime<Local>) -> TaskStats {
+        let today = now.date_naive();
+        let mut stats = TaskStats {
+            total: self.tasks.len(),
+            pending: self.tasks.iter().filter(|t| t.status == TaskStatus::Pending).count(),
+            completed: self.tasks.iter().filter(|t| t.status == TaskStatus::Completed).count(),
+            overdue: self.overdue_tasks(today).len(),
+            ..TaskStats::default()
+        };
+        
+        // Count tasks by due date, and by the week (starting on Monday) of their due date
+        for task in &self.tasks {
+            if let Some(date) = task.due_date {
+                *stats.by_date.entry(date).or_insert(0) += 1;
+                let week_start = date - Duration::days(i64::from(date.weekday().num_days_from_monday()));
+                *stats.by_week.entry(week_start).or_insert(0) += 1;
+            }
+        }
+        
+        // Tasks created before timestamps were recorded are left out of the average
+        let durations: Vec<Duration> = self.tasks.iter().filter_map(Task::time_to_complete).collect();
+        if !durations.is_empty() {
+            let total: Duration = durations.iter().copied().sum();
+            stats.average_time_to_complete = Some(total / durations.len() as i32);
+            stats.timed_completions = durations.len();
+        }
+        
+        for task in self.tasks.iter().filter(|task| !task.time_entries.is_empty()) {
+            stats.time_by_task.push(TrackedTime {
+                id: task.id,
+                title: task.title.clone(),
+                total: task.time_spent(now),
+                running: task.running_since().is_some(),
+            });
+            for (date, duration) in task.time_entries.iter().flat_map(|entry| entry.by_day(now)) {
+                *stats.time_by_day.entry(date).or_insert_with(Duration::zero) += duration;
+            }
+        }
+        stats.time_by_task.sort_by(|a, b| b.total.cmp(&a.total).then(a.id.cmp(&b.id)));
+        
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_manager::reminders::{Clock, ReminderKind};
+    use crate::task_manager::storage::{parse_task_file, SqliteStorage};
+    use crate::task_manager::task::Priority;
+    use crate::task_manager::testing::{due_task, task, FixedClock, TempDir};
+
+    fn reminded_ids(reminders: &[Reminder]) -> Vec<(u32, ReminderKind)> {
+        reminders.iter().map(|reminder| (reminder.task.id, reminder.kind)).collect()
+    }
+
+    fn new_task(title: &str) -> NewTask {
+        NewTask { title: title.to_string(), ..NewTask::default() }
+    }
+
+    fn now() -> DateTime<Local> {
+        FixedClock::at("2026-10-19T09:00:00+00:00").now()
+    }
+
+    #[test]
+    fn running_out_of_task_ids_is_an_error() {
+        let (file, _) = parse_task_file(r#"{"next_id": 4294967295, "tasks": []}"#).unwrap();
+        let mut manager = TaskManager::new();
+        manager.restore(file);
+        assert_eq!(manager.add_task(new_task("Last"), now()).unwrap_err(), TaskError::IdOutOfRange(u32::MAX));
+        assert!(manager.tasks().is_empty());
+
+        manager.next_id = u32::MAX;
+        let mut daily = task(1, "Daily");
+        daily.recurrence = Some(Recurrence::Daily);
+        manager.tasks = vec![daily.clone()];
+        assert_eq!(manager.complete_task(1, now()).unwrap_err(), TaskError::IdOutOfRange(u32::MAX));
+        assert_eq!(manager.tasks(), [daily]);
+    }
+
+    #[test]
+    fn overdue_tasks_are_pending_and_due_before_today() {
+        let today = now().date_naive();
+        let mut manager = TaskManager::new();
+        let mut done = due_task(3, "Done late", "2026-10-01");
+        done.status = TaskStatus::Completed;
+        manager.tasks = vec![
+            due_task(1, "Late", "2026-10-18"),
+            due_task(2, "Today", "2026-10-19"),
+            done,
+            task(4, "Undated"),
+        ];
+
+        let ids = |tasks: Vec<&Task>| tasks.iter().map(|task| task.id).collect::<Vec<_>>();
+        assert_eq!(ids(manager.overdue_tasks(today)), [1]);
+        assert_eq!(ids(manager.filter_due_before(today)), [1, 3]);
+        assert_eq!(ids(manager.filter_by_due_date(today)), [2]);
+    }
+
+    #[test]
+    fn reminders_cover_overdue_due_today_and_due_within_the_lead_time() {
+        let clock = FixedClock::at("2026-10-19T09:00:00+00:00");
+        let mut manager = TaskManager::new();
+        let mut done = due_task(5, "Done", "2026-10-01");
+        done.status = TaskStatus::Completed;
+        manager.tasks = vec![
+            due_task(1, "Later", "2026-10-25"),
+            due_task(2, "Tomorrow", "2026-10-20"),
+            due_task(3, "Today", "2026-10-19"),
+            due_task(4, "Late", "2026-10-12"),
+            done,
+            task(6, "Undated"),
+        ];
+
+        let reminders = manager.reminders(clock.now());
+        assert_eq!(
+            reminded_ids(&reminders),
+            vec![(4, ReminderKind::Overdue), (3, ReminderKind::DueToday), (2, ReminderKind::DueSoon)]
+        );
+        assert_eq!(reminders[0].describe(clock.now().date_naive()), "Overdue: #4 Late (due 2026-10-12, 7 days ago)");
+
+        manager.reminders.lead_time = Duration::weeks(1);
+        assert_eq!(manager.reminders(clock.now()).len(), 4);
+
+        // A lead time reaching past the last representable date covers every due date
+        manager.tasks.push(due_task(7, "Far off", "9999-12-31"));
+        manager.reminders.lead_time = Duration::MAX;
+        assert_eq!(manager.reminders(clock.now()).len(), 5);
+    }
+
+    #[test]
+    fn new_reminders_are_shown_once_until_a_snooze_ends() {
+        let clock = FixedClock::at("2026-10-19T09:00:00+00:00");
+        let mut manager = TaskManager::new();
+        manager.tasks = vec![due_task(1, "Today", "2026-10-19"), due_task(2, "Late", "2026-10-18")];
+
+        assert_eq!(manager.new_reminders(clock.now()).len(), 2);
+        assert!(manager.new_reminders(clock.now()).is_empty());
+
+        manager.snooze(1, Some(clock.now() + Duration::hours(2))).unwrap();
+        clock.advance(Duration::hours(1));
+        assert!(manager.reminders(clock.now()).iter().all(|reminder| reminder.task.id != 1));
+        assert!(manager.new_reminders(clock.now()).is_empty());
+
+        clock.advance(Duration::hours(1));
+        assert_eq!(reminded_ids(&manager.new_reminders(clock.now())), vec![(1, ReminderKind::DueToday)]);
+
+        // Overnight, the task due today becomes overdue and is reminded of again
+        clock.advance(Duration::days(1));
+        assert_eq!(reminded_ids(&manager.new_reminders(clock.now())), vec![(1, ReminderKind::Overdue)]);
+    }
+
+    #[test]
+    fn no_new_reminders_while_reminders_are_off() {
+        let clock = FixedClock::at("2026-10-19T09:00:00+00:00");
+        let mut manager = TaskManager::new();
+        manager.tasks = vec![due_task(1, "Today", "2026-10-19")];
+        manager.reminders.enabled = false;
+        assert!(manager.new_reminders(clock.now()).is_empty());
+        assert_eq!(manager.reminders(clock.now()).len(), 1);
+    }
+
+    #[test]
+    fn operations_on_missing_tasks_say_which_task() {
+        let mut manager = TaskManager::new();
+        assert_eq!(manager.complete_task(7, now()).unwrap_err(), TaskError::NotFound(7));
+        assert_eq!(manager.delete_task(7), Err(TaskError::NotFound(7)));
+        assert_eq!(manager.snooze(7, None), Err(TaskError::NotFound(7)));
+        assert_eq!(manager.undo(), Err(TaskError::NothingToUndo));
+        assert_eq!(manager.redo(), Err(TaskError::NothingToRedo));
+        assert_eq!(TaskError::NotFound(7).to_string(), "Task #7 not found");
+    }
+
+    #[test]
+    fn edits_report_the_fields_that_changed() {
+        let mut manager = TaskManager::new();
+        manager.tasks = vec![task(1, "Buy milk")];
+        let update = TaskUpdate {
+            title: Some("Buy milk".to_string()),
+            description: Some("Oat milk".to_string()),
+            priority: Some(Priority::High),
+            status: Some(TaskStatus::Completed),
+            ..TaskUpdate::default()
+        };
+        let (changed, next) = manager.update_task(1, update, now()).unwrap();
+        assert_eq!(changed, ["description", "status", "priority"]);
+        assert!(next.is_none());
+        assert_eq!(manager.tasks()[0].completed_at, Some(now()));
+
+        let update = TaskUpdate { status: Some(TaskStatus::Pending), ..TaskUpdate::default() };
+        assert_eq!(manager.update_task(1, update, now()).unwrap().0, ["status"]);
+        assert_eq!(manager.tasks()[0].completed_at, None);
+        let update = TaskUpdate { priority: Some(Priority::High), ..TaskUpdate::default() };
+        assert!(manager.update_task(1, update, now()).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn tasks_need_a_title() {
+        let mut manager = TaskManager::new();
+        for title in ["", " \t "] {
+            assert_eq!(manager.add_task(new_task(title), now()).unwrap_err(), TaskError::EmptyTitle);
+        }
+        assert!(manager.tasks().is_empty());
+        assert_eq!(manager.add_task(new_task("Buy milk"), now()).unwrap().id, 1);
+    }
+
+    #[test]
+    fn invalid_edits_change_nothing() {
+        let mut manager = TaskManager::new();
+        manager.tasks = vec![task(1, "Buy milk")];
+        let update = TaskUpdate {
+            title: Some("  ".to_string()),
+            description: Some("Oat milk".to_string()),
+            ..TaskUpdate::default()
+        };
+        assert_eq!(manager.update_task(1, update, now()).unwrap_err(), TaskError::EmptyTitle);
+        let update = TaskUpdate {
+            description: Some("Oat milk".to_string()),
+            depends_on: Some(vec![1]),
+            ..TaskUpdate::default()
+        };
+        assert_eq!(manager.update_task(1, update, now()).unwrap_err(), TaskError::OwnDependency(1));
+        assert_eq!(manager.tasks(), [task(1, "Buy milk")]);
+        assert_eq!(manager.undo().unwrap_err(), TaskError::NothingToUndo);
+    }
+
+    #[test]
+    fn links_that_would_loop_are_refused() {
+        let mut manager = TaskManager::new();
+        manager.add_task(new_task("Parent"), now()).unwrap();
+        manager.add_task(NewTask { parent: Some(1), depends_on: vec![1], ..new_task("Child") }, now()).unwrap();
+
+        let update = TaskUpdate { parent: Some(Some(2)), ..TaskUpdate::default() };
+        assert_eq!(manager.update_task(1, update, now()), Err(TaskError::SubtaskLoop { id: 1, parent: 2 }));
+        let update = TaskUpdate { depends_on: Some(vec![2]), ..TaskUpdate::default() };
+        let error = manager.update_task(1, update, now()).unwrap_err();
+        assert_eq!(error, TaskError::DependencyCycle(vec![1, 2, 1]));
+        assert_eq!(error.to_string(), "Dependency cycle: #1 -> #2 -> #1");
+        let parentless = NewTask { parent: Some(9), ..new_task("Orphan") };
+        assert_eq!(manager.add_task(parentless, now()).unwrap_err(), TaskError::ParentNotFound(9));
+    }
+
+    #[test]
+    fn tasks_wait_for_their_dependencies() {
+        let mut manager = TaskManager::new();
+        manager.add_task(new_task("First"), now()).unwrap();
+        manager.add_task(NewTask { depends_on: vec![1], ..new_task("Second") }, now()).unwrap();
+
+        assert_eq!(manager.complete_task(2, now()).unwrap_err(), TaskError::Blocked { id: 2, blockers: vec![1] });
+        manager.complete_task(1, now()).unwrap();
+        manager.complete_task(2, now()).unwrap();
+        assert!(manager.tasks().iter().all(|task| task.status == TaskStatus::Completed));
+    }
+
+    #[test]
+    fn completing_a_recurring_task_schedules_the_next_one() {
+        let mut manager = TaskManager::new();
+        let weekly = NewTask {
+            due_date: NaiveDate::from_ymd_opt(2026, 10, 12),
+            recurrence: Some(Recurrence::Weekly { weekdays: Vec::new() }),
+            ..new_task("Water plants")
+        };
+        manager.add_task(weekly, now()).unwrap();
+
+        // The missed occurrence on the 19th is today, so it is not skipped
+        let next = manager.complete_task(1, now()).unwrap().unwrap();
+        assert_eq!(next.id, 2);
+        assert_eq!(next.due_date, NaiveDate::from_ymd_opt(2026, 10, 19));
+        assert_eq!(manager.find_task(1).unwrap().recurrence, None);
+
+        // Undo removes the new occurrence as well
+        manager.undo().unwrap();
+        assert_eq!(manager.tasks().len(), 1);
+        assert_eq!(manager.tasks()[0].status, TaskStatus::Pending);
+        assert!(manager.tasks()[0].recurrence.is_some());
+    }
+
+    #[test]
+    fn completing_a_recurring_task_by_editing_its_status_schedules_the_next_one() {
+        let mut manager = TaskManager::new();
+        let daily = NewTask {
+            due_date: NaiveDate::from_ymd_opt(2026, 10, 19),
+            recurrence: Some(Recurrence::Daily),
+            ..new_task("Stretch")
+        };
+        manager.add_task(daily, now()).unwrap();
+
+        let update = TaskUpdate { status: Some(TaskStatus::Completed), ..TaskUpdate::default() };
+        let (changed, next) = manager.update_task(1, update, now()).unwrap();
+        assert_eq!(changed, ["status"]);
+        let next = next.unwrap();
+        assert_eq!((next.id, next.status.clone()), (2, TaskStatus::Pending));
+        assert_eq!(next.due_date, NaiveDate::from_ymd_opt(2026, 10, 20));
+        assert_eq!(next.completed_at, None);
+        assert_eq!(manager.find_task(1).unwrap().recurrence, None);
+
+        // Reopening the task does not schedule anything else
+        let update = TaskUpdate { status: Some(TaskStatus::Pending), ..TaskUpdate::default() };
+        assert_eq!(manager.update_task(1, update, now()).unwrap().1, None);
+
+        manager.undo().unwrap();
+        manager.undo().unwrap();
+        assert_eq!(manager.tasks().len(), 1);
+        assert_eq!(manager.tasks()[0].recurrence, Some(Recurrence::Daily));
+    }
+
+    #[test]
+    fn upcoming_lists_every_occurrence_in_range() {
+        let today = now().date_naive();
+        let mut manager = TaskManager::new();
+        let mut daily = due_task(1, "Stretch", "2026-10-18");
+        daily.recurrence = Some(Recurrence::Daily);
+        let mut every_third = due_task(2, "Water plants", "2026-10-20");
+        every_third.recurrence = Some(Recurrence::EveryNDays { days: 3 });
+        let mut done = due_task(4, "Done", "2026-10-20");
+        done.status = TaskStatus::Completed;
+        manager.tasks = vec![daily, every_third, due_task(3, "Dentist", "2026-10-21"), done, due_task(5, "Later", "2026-11-30")];
+
+        let occurrences: Vec<(u32, String)> = manager.upcoming(today, today + Duration::days(4))
+            .iter()
+            .map(|occurrence| (occurrence.task.id, occurrence.date.to_string()))
+            .collect();
+        let expected = [
+            (1, "2026-10-19"), (1, "2026-10-20"), (2, "2026-10-20"), (1, "2026-10-21"),
+            (3, "2026-10-21"), (1, "2026-10-22"), (1, "2026-10-23"), (2, "2026-10-23"),
+        ];
+        assert_eq!(occurrences, expected.map(|(id, date)| (id, date.to_string())));
+    }
+
+    #[test]
+    fn undo_and_redo_replay_changes_in_order() {
+        let mut manager = TaskManager::new();
+        manager.add_task(new_task("First"), now()).unwrap();
+        manager.add_task(new_task("Second"), now()).unwrap();
+        manager.delete_task(1).unwrap();
+
+        assert_eq!(manager.undo(), Ok("delete of task #1".to_string()));
+        assert_eq!(manager.undo(), Ok("add of task #2".to_string()));
+        assert_eq!(manager.tasks().len(), 1);
+        assert_eq!(manager.redo(), Ok("add of task #2".to_string()));
+        let ids: Vec<u32> = manager.tasks().iter().map(|task| task.id).collect();
+        assert_eq!(ids, [1, 2]);
+
+        // A new change makes the undone delete impossible to redo
+        manager.add_task(new_task("Third"), now()).unwrap();
+        assert_eq!(manager.redo(), Err(TaskError::NothingToRedo));
+    }
+
+    #[test]
+    fn stats_group_due_dates_by_week_and_total_tracked_time() {
+        let monday_of = |day| NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+        let mut monday = due_task(1, "Monday", "2026-10-19");
+        monday.time_entries.push(TimeEntry { start: now() - Duration::hours(3), end: Some(now() - Duration::hours(2)) });
+        let mut wednesday = due_task(2, "Wednesday", "2026-10-21");
+        wednesday.status = TaskStatus::Completed;
+        wednesday.created_at = Some(now() - Duration::days(2));
+        wednesday.completed_at = Some(now() - Duration::days(1));
+        let mut next_week = due_task(3, "Next week", "2026-10-26");
+        next_week.time_entries.push(TimeEntry { start: now() - Duration::hours(2), end: None });
+        let mut undated = task(4, "Undated");
+        undated.status = TaskStatus::Completed;
+        let mut manager = TaskManager::new();
+        manager.tasks = vec![monday, wednesday, next_week, undated];
+
+        let stats = manager.generate_stats(now());
+        assert_eq!((stats.total, stats.pending, stats.completed), (4, 2, 2));
+        assert_eq!(stats.completion_rate(), Some(50.0));
+        assert_eq!(stats.by_date.len(), 3);
+        assert_eq!(stats.by_week, BTreeMap::from([(monday_of(19), 2), (monday_of(26), 1)]));
+        // The undated task has no timestamps, so only one completion is timed
+        assert_eq!(stats.average_time_to_complete, Some(Duration::days(1)));
+        assert_eq!(stats.timed_completions, 1);
+        let tracked: Vec<(u32, Duration, bool)> = stats.time_by_task.iter()
+            .map(|time| (time.id, time.total, time.running))
+            .collect();
+        assert_eq!(tracked, [(3, Duration::hours(2), true), (1, Duration::hours(1), false)]);
+        assert_eq!(stats.time_by_day.values().copied().sum::<Duration>(), Duration::hours(3));
+
+        assert_eq!(TaskManager::new().generate_stats(now()).completion_rate(), None);
+    }
+
+    #[test]
+    fn only_one_timer_runs_unless_asked_for_more() {
+        let mut manager = TaskManager::new();
+        manager.add_task(new_task("First"), now()).unwrap();
+        manager.add_task(new_task("Second"), now()).unwrap();
+
+        manager.start_timer(1, now(), false).unwrap();
+        assert_eq!(manager.start_timer(1, now(), false), Err(TaskError::AlreadyRunning(1)));
+        assert_eq!(manager.start_timer(2, now(), false), Err(TaskError::TimersRunning(vec![1])));
+        manager.start_timer(2, now(), true).unwrap();
+
+        assert_eq!(manager.stop_timer(1, now() + Duration::minutes(25)), Ok(Duration::minutes(25)));
+        assert_eq!(manager.stop_timer(1, now()), Err(TaskError::NotRunning(1)));
+    }
+
+    #[test]
+    fn projects_keep_their_own_tasks() {
+        let mut manager = TaskManager::new();
+        manager.add_task(new_task("Default task"), now()).unwrap();
+        manager.create_project("home").unwrap();
+        assert_eq!(manager.create_project("home"), Err(TaskError::ProjectExists("home".to_string())));
+        assert_eq!(manager.create_project("my home"), Err(TaskError::InvalidProjectName("my home".to_string())));
+
+        manager.move_task(1, "home").unwrap();
+        assert!(manager.tasks().is_empty());
+        manager.switch_project("home").unwrap();
+        assert_eq!(manager.project(), "home");
+        assert_eq!(manager.tasks()[0].title, "Default task");
+
+        assert_eq!(manager.delete_project("home", false), Err(TaskError::CurrentProject("home".to_string())));
+        manager.switch_project(DEFAULT_PROJECT).unwrap();
+        assert_eq!(
+            manager.delete_project("home", false),
+            Err(TaskError::ProjectNotEmpty { name: "home".to_string(), tasks: 1 })
+        );
+        assert_eq!(manager.delete_project("home", true), Ok(1));
+        assert_eq!(manager.rename_project(DEFAULT_PROJECT, "main"), Err(TaskError::CannotRenameDefault));
+    }
+
+    #[test]
+    fn snapshots_restore_every_project() {
+        let mut manager = TaskManager::new();
+        manager.add_task(new_task("First"), now()).unwrap();
+        manager.create_project("work").unwrap();
+        manager.switch_project("work").unwrap();
+        manager.add_task(new_task("Second"), now()).unwrap();
+
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        storage.save(&manager.snapshot()).unwrap();
+        let mut loaded = TaskManager::new();
+        loaded.load_from(&storage, true).unwrap().unwrap();
+        assert_eq!(loaded.project(), "work");
+        assert_eq!(loaded.next_id, 3);
+        assert_eq!(loaded.tasks, manager.tasks);
+        assert_eq!(loaded.other_projects, manager.other_projects);
+    }
+
+    fn titles(manager: &TaskManager) -> Vec<&str> {
+        manager.tasks().iter().map(|task| task.title.as_str()).collect()
+    }
+
+    fn saved_titles(filename: &str) -> Vec<String> {
+        let mut manager = TaskManager::new();
+        manager.load_from_file(filename, true).unwrap();
+        manager.tasks().iter().map(|task| task.title.clone()).collect()
+    }
+
+    #[test]
+    fn autosave_can_be_bound_to_the_loaded_file() {
+        let dir = TempDir::new("autosave-loaded");
+        let file = dir.file("tasks.json");
+        let mut manager = TaskManager::new();
+        manager.add_task(new_task("Saved"), now()).unwrap();
+        manager.save_to_file(&file, StorageFormat::Json).unwrap();
+
+        let mut manager = TaskManager::new();
+        manager.load_from_file(&file, true).unwrap();
+        manager.add_task(new_task("Added after loading"), now()).unwrap();
+        assert!(manager.enable_autosave(&file).unwrap().is_none());
+        assert_eq!(saved_titles(&file), ["Saved", "Added after loading"]);
+
+        // Turning autosave off and on again keeps the same file
+        manager.set_storage(None);
+        manager.add_task(new_task("Added while off"), now()).unwrap();
+        manager.enable_autosave(&file).unwrap();
+        assert_eq!(saved_titles(&file).len(), 3);
+    }
+
+    #[test]
+    fn loading_another_file_moves_autosave_to_it() {
+        let dir = TempDir::new("autosave-load");
+        let (a, b) = (dir.file("a.json"), dir.file("b.json"));
+        let mut other = TaskManager::new();
+        other.add_task(new_task("Bonly"), now()).unwrap();
+        other.save_to_file(&b, StorageFormat::Json).unwrap();
+
+        let mut manager = TaskManager::new();
+        manager.enable_autosave(&a).unwrap();
+        manager.add_task(new_task("Aonly"), now()).unwrap();
+        manager.autosave().unwrap();
+
+        manager.load_from_file(&b, true).unwrap();
+        assert!(manager.autosaving());
+        manager.add_task(new_task("Added to B"), now()).unwrap();
+        manager.autosave().unwrap();
+        assert_eq!(saved_titles(&a), ["Aonly"]);
+        assert_eq!(saved_titles(&b), ["Bonly", "Added to B"]);
+
+        // Restoring tasks from elsewhere turns autosave off
+        manager.restore(TaskFile { next_id: 1, tasks: Vec::new(), projects: BTreeMap::new(), project: None });
+        assert!(!manager.autosaving());
+    }
+
+    #[test]
+    fn autosave_does_not_overwrite_files_with_other_tasks() {
+        let dir = TempDir::new("autosave-refused");
+        let file = dir.file("tasks.json");
+        let mut saved = TaskManager::new();
+        saved.add_task(new_task("On disk"), now()).unwrap();
+        saved.save_to_file(&file, StorageFormat::Json).unwrap();
+
+        // Tasks in a project other than the current one count too
+        let mut manager = TaskManager::new();
+        manager.create_project("work").unwrap();
+        manager.switch_project("work").unwrap();
+        manager.add_task(new_task("At work"), now()).unwrap();
+        manager.switch_project(DEFAULT_PROJECT).unwrap();
+        assert_eq!(manager.enable_autosave(&file).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        assert!(!manager.autosaving());
+        assert_eq!(saved_titles(&file), ["On disk"]);
+
+        // With no tasks, the file is loaded instead
+        let mut manager = TaskManager::new();
+        assert_eq!(manager.enable_autosave(&file).unwrap().map(|report| report.loaded), Some(1));
+        assert_eq!(titles(&manager), ["On disk"]);
+    }
+
+    // A task file with one line that cannot be loaded
+    struct DamagedStorage;
+
+    impl Storage for DamagedStorage {
+        fn load(&self) -> io::Result<Option<(TaskFile<Vec<Task>>, LoadReport)>> {
+            parse_task_file("3\n1|Good|Fine|none|pending\n2|Bad|Broken|none|done\n").map(Some)
+        }
+
+        fn save(&self, _file: &TaskFile<&[Task]>) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn invalid_lines_are_refused_in_strict_mode() {
+        let mut strict = TaskManager::new();
+        strict.tasks = vec![task(9, "Kept")];
+        assert!(strict.load_from(&DamagedStorage, true).is_err());
+        assert_eq!(strict.tasks, vec![task(9, "Kept")]);
+
+        let mut lenient = TaskManager::new();
+        let report = lenient.load_from(&DamagedStorage, false).unwrap().unwrap();
+        assert_eq!(report.loaded, 1);
+        assert!(report.skipped[0].reason.contains("Unknown status 'done'"));
+        assert_eq!(lenient.tasks().len(), 1);
+        assert_eq!(lenient.tasks()[0].title, "Good");
+    }
+}
@@ -0,0 +1,23 @@
+// The task manager as a library: tasks, projects and their storage, plus the command
+// parser and import/export. Nothing here prints; the REPL in starter_code.rs shows results.
+
+mod command;
+mod error;
+mod exchange;
+mod manager;
+mod query;
+mod reminders;
+mod search;
+mod storage;
+mod task;
+#[cfg(test)]
+mod testing;
+
+pub use command::*;
+pub use error::*;
+pub use exchange::*;
+pub use manager::*;
+pub use reminders::*;
+pub use search::*;
+pub use storage::*;
+pub use task::*;
@@ -0,0 +1,167 @@
+// Filtering and sorting of task lists
+
+use std::cmp::Ordering;
+use chrono::NaiveDate;
+
+use super::task::{Priority, Task, TaskStatus};
+
+// How a filter compares a task's value with the value in the query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    pub fn matches<T: Ord>(self, actual: &T, expected: &T) -> bool {
+        match self {
+            Comparison::Equal => actual == expected,
+            Comparison::NotEqual => actual != expected,
+            Comparison::Less => actual < expected,
+            Comparison::LessOrEqual => actual <= expected,
+            Comparison::Greater => actual > expected,
+            Comparison::GreaterOrEqual => actual >= expected,
+        }
+    }
+}
+
+// One condition of a `list` query, such as `tag:work` or `priority>=high`
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterTerm {
+    Status(Comparison, TaskStatus),
+    Tag(Comparison, String),
+    Priority(Comparison, Priority),
+    // `None` matches tasks without a due date (`due:none`)
+    Due(Comparison, Option<NaiveDate>),
+}
+
+impl FilterTerm {
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            // Status, tag and `due:none` terms only accept equality operators
+            FilterTerm::Status(Comparison::NotEqual, status) => task.status != *status,
+            FilterTerm::Status(_, status) => task.status == *status,
+            FilterTerm::Tag(Comparison::NotEqual, tag) => !task.has_tag(tag),
+            FilterTerm::Tag(_, tag) => task.has_tag(tag),
+            FilterTerm::Due(Comparison::NotEqual, None) => task.due_date.is_some(),
+            FilterTerm::Due(_, None) => task.due_date.is_none(),
+            FilterTerm::Priority(cmp, priority) => cmp.matches(&task.priority, priority),
+            // Tasks without a due date never match a date comparison
+            FilterTerm::Due(cmp, Some(date)) => task.due_date.is_some_and(|due| cmp.matches(&due, date)),
+        }
+    }
+}
+
+// Field used to order task listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    #[default]
+    Id,
+    Title,
+    Due,
+    Priority,
+}
+
+impl SortKey {
+    pub fn parse(name: &str) -> Option<SortKey> {
+        match name.to_lowercase().as_str() {
+            "id" => Some(SortKey::Id),
+            "title" => Some(SortKey::Title),
+            "due" => Some(SortKey::Due),
+            "priority" => Some(SortKey::Priority),
+            _ => None,
+        }
+    }
+}
+
+// Ordering of a task listing; ties are broken by task ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TaskSort {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl TaskSort {
+    pub fn compare(&self, a: &Task, b: &Task) -> Ordering {
+        // Tasks without a due date come last in either direction
+        if self.key == SortKey::Due && a.due_date.is_some() != b.due_date.is_some() {
+            return if a.due_date.is_some() { Ordering::Less } else { Ordering::Greater };
+        }
+
+        let ordering = match self.key {
+            SortKey::Id => Ordering::Equal,
+            SortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            SortKey::Due => a.due_date.cmp(&b.due_date),
+            SortKey::Priority => a.priority.cmp(&b.priority),
+        }
+        .then(a.id.cmp(&b.id));
+
+        if self.descending { ordering.reverse() } else { ordering }
+    }
+
+    pub fn sort(&self, tasks: &mut [&Task]) {
+        tasks.sort_by(|a, b| self.compare(a, b));
+    }
+}
+
+// Conditions combined with AND; an empty filter matches every task
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskFilter {
+    pub terms: Vec<FilterTerm>,
+}
+
+impl TaskFilter {
+    pub fn matches(&self, task: &Task) -> bool {
+        self.terms.iter().all(|term| term.matches(task))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_manager::testing::{due_task, task};
+
+    #[test]
+    fn filters_combine_their_terms() {
+        let mut urgent = due_task(1, "Urgent", "2026-10-20");
+        urgent.priority = Priority::Urgent;
+        urgent.tags = vec!["Work".to_string()];
+        let undated = task(2, "Undated");
+
+        let filter = TaskFilter {
+            terms: vec![
+                FilterTerm::Tag(Comparison::Equal, "work".to_string()),
+                FilterTerm::Priority(Comparison::GreaterOrEqual, Priority::High),
+            ],
+        };
+        assert!(filter.matches(&urgent));
+        assert!(!filter.matches(&undated));
+        assert!(TaskFilter::default().matches(&undated));
+
+        let before = NaiveDate::from_ymd_opt(2026, 10, 21);
+        assert!(FilterTerm::Due(Comparison::Less, before).matches(&urgent));
+        assert!(!FilterTerm::Due(Comparison::Less, before).matches(&undated));
+        assert!(FilterTerm::Due(Comparison::Equal, None).matches(&undated));
+    }
+
+    #[test]
+    fn undated_tasks_sort_last_either_way() {
+        let tasks = [task(1, "b"), due_task(2, "a", "2026-10-21"), due_task(3, "c", "2026-10-20")];
+        let mut listing: Vec<&Task> = tasks.iter().collect();
+        for (descending, expected) in [(false, [3, 2, 1]), (true, [2, 3, 1])] {
+            TaskSort { key: SortKey::Due, descending }.sort(&mut listing);
+            let ids: Vec<u32> = listing.iter().map(|task| task.id).collect();
+            assert_eq!(ids, expected);
+        }
+        TaskSort { key: SortKey::Title, descending: false }.sort(&mut listing);
+        assert_eq!(listing[0].id, 2);
+    }
+}
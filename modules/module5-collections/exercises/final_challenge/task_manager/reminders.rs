@@ -0,0 +1,90 @@
+// Reminders about tasks that are due or overdue
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use chrono::{DateTime, Duration, Local, NaiveDate};
+
+use super::task::{Task, TaskStatus, DATE_FORMAT};
+
+// Source of the current time for the REPL, so behavior that depends on it (such as
+// reminders) can be driven by a fixed time in tests
+pub trait Clock {
+    fn now(&self) -> DateTime<Local>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+// Why a task is being reminded of, from most to least urgent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReminderKind {
+    Overdue,
+    DueToday,
+    // Due within the reminder lead time
+    DueSoon,
+}
+
+// A pending task to remind the user of
+#[derive(Debug)]
+pub struct Reminder<'a> {
+    pub kind: ReminderKind,
+    pub due: NaiveDate,
+    pub task: &'a Task,
+}
+
+impl Reminder<'_> {
+    pub fn describe(&self, today: NaiveDate) -> String {
+        let days = (self.due - today).num_days().abs();
+        let days = if days == 1 { "1 day".to_string() } else { format!("{} days", days) };
+        let due = self.due.format(DATE_FORMAT);
+        match self.kind {
+            ReminderKind::Overdue => format!("Overdue: #{} {} (due {}, {} ago)", self.task.id, self.task.title, due, days),
+            ReminderKind::DueToday => format!("Due today: #{} {}", self.task.id, self.task.title),
+            ReminderKind::DueSoon => format!("Due soon: #{} {} (due {}, in {})", self.task.id, self.task.title, due, days),
+        }
+    }
+}
+
+// Which reminders the REPL shows, and which it has shown already
+pub struct Reminders {
+    pub enabled: bool,
+    // How long before its due date a pending task is reminded of
+    pub lead_time: Duration,
+    // Each reminder is shown once rather than before every prompt. A new due date
+    // or an ended snooze makes a task's reminder new again.
+    pub(super) shown: HashSet<(u32, ReminderKind, NaiveDate, Option<DateTime<Local>>)>,
+}
+
+impl Default for Reminders {
+    fn default() -> Reminders {
+        Reminders { enabled: true, lead_time: Duration::days(1), shown: HashSet::new() }
+    }
+}
+
+// Reminders for pending tasks that are overdue, due today or due within `lead_time`,
+// most urgent first. Snoozed tasks are left out until their snooze ends.
+pub fn collect_reminders(tasks: &[Task], lead_time: Duration, now: DateTime<Local>) -> Vec<Reminder<'_>> {
+    let today = now.date_naive();
+    let horizon = now.checked_add_signed(lead_time).map_or(NaiveDate::MAX, |horizon| horizon.date_naive());
+    let mut reminders: Vec<Reminder> = tasks.iter()
+        .filter(|task| task.status == TaskStatus::Pending)
+        .filter(|task| task.snoozed_until.is_none_or(|until| until <= now))
+        .filter_map(|task| {
+            let due = task.due_date?;
+            let kind = match due.cmp(&today) {
+                Ordering::Less => ReminderKind::Overdue,
+                Ordering::Equal => ReminderKind::DueToday,
+                Ordering::Greater if due <= horizon => ReminderKind::DueSoon,
+                Ordering::Greater => return None,
+            };
+            Some(Reminder { kind, due, task })
+        })
+        .collect();
+    reminders.sort_by_key(|reminder| (reminder.kind, reminder.due, reminder.task.id));
+    reminders
+}
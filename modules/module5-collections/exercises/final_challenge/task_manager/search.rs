@@ -0,0 +1,156 @@
+// Fuzzy search over task titles and descriptions
+
+use std::ops::Range;
+
+use super::task::Task;
+
+// How well a search term matches a word, from weakest to strongest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum WordMatch {
+    // Within a small edit distance, to tolerate typos
+    Fuzzy,
+    Prefix,
+    Exact,
+}
+
+impl WordMatch {
+    fn find(term: &str, word: &str) -> Option<WordMatch> {
+        if word == term {
+            return Some(WordMatch::Exact);
+        }
+        if word.starts_with(term) {
+            return Some(WordMatch::Prefix);
+        }
+        // Short terms would fuzzy-match too many unrelated words
+        let allowed = match term.chars().count() {
+            0..=3 => return None,
+            4..=7 => 1,
+            _ => 2,
+        };
+        (edit_distance(term, word) <= allowed).then_some(WordMatch::Fuzzy)
+    }
+
+    fn score(self) -> u32 {
+        match self {
+            WordMatch::Fuzzy => 1,
+            WordMatch::Prefix => 2,
+            WordMatch::Exact => 3,
+        }
+    }
+}
+
+// Number of single-character insertions, deletions and substitutions turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Byte ranges of the words (runs of letters and digits) in `text`
+fn word_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(index),
+            (false, Some(begin)) => {
+                ranges.push(begin..index);
+                start = None;
+            },
+            _ => {},
+        }
+    }
+    if let Some(begin) = start {
+        ranges.push(begin..text.len());
+    }
+    ranges
+}
+
+// A task found by `TaskManager::search`, with the words that matched
+#[derive(Debug)]
+pub struct SearchHit<'a> {
+    pub task: &'a Task,
+    pub score: u32,
+    pub title_matches: Vec<Range<usize>>,
+    pub description_matches: Vec<Range<usize>>,
+}
+
+// Tasks whose title or description matches every word of the query, best matches first.
+// Words match case-insensitively, as a prefix, or within a small edit distance; matches
+// in the title count double.
+pub fn search_tasks<'a>(tasks: &'a [Task], query: &str) -> Vec<SearchHit<'a>> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    
+    let mut hits = Vec::new();
+    for task in tasks {
+        let mut hit = SearchHit { task, score: 0, title_matches: Vec::new(), description_matches: Vec::new() };
+        let fields = [(&task.title, 2), (&task.description, 1)];
+        let all_terms_match = terms.iter().all(|term| {
+            let mut best = 0;
+            for (field, (text, weight)) in fields.iter().enumerate() {
+                for range in word_ranges(text) {
+                    let Some(found) = WordMatch::find(term, &text[range.clone()].to_lowercase()) else {
+                        continue;
+                    };
+                    best = best.max(found.score() * weight);
+                    let matches = if field == 0 { &mut hit.title_matches } else { &mut hit.description_matches };
+                    if !matches.contains(&range) {
+                        matches.push(range);
+                    }
+                }
+            }
+            hit.score += best;
+            best > 0
+        });
+        if all_terms_match {
+            hit.title_matches.sort_by_key(|range| range.start);
+            hit.description_matches.sort_by_key(|range| range.start);
+            hits.push(hit);
+        }
+    }
+    hits.sort_by(|a, b| b.score.cmp(&a.score).then(a.task.id.cmp(&b.task.id)));
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_manager::testing::task;
+
+    #[test]
+    fn words_match_exactly_by_prefix_or_with_typos() {
+        assert_eq!(WordMatch::find("milk", "milk"), Some(WordMatch::Exact));
+        assert_eq!(WordMatch::find("mil", "milk"), Some(WordMatch::Prefix));
+        assert_eq!(WordMatch::find("groceries", "grocereis"), Some(WordMatch::Fuzzy));
+        assert_eq!(WordMatch::find("mlk", "milk"), None);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(word_ranges("Buy oat-milk!"), [0..3, 4..7, 8..12]);
+    }
+
+    #[test]
+    fn title_matches_rank_first() {
+        let mut in_description = task(1, "Shopping");
+        in_description.description = "Milk and bread".to_string();
+        let in_title = task(2, "Milk");
+        let unrelated = task(3, "Laundry");
+        let tasks = [in_description, in_title, unrelated];
+
+        let hits = search_tasks(&tasks, "MILK");
+        let ids: Vec<u32> = hits.iter().map(|hit| hit.task.id).collect();
+        assert_eq!(ids, [2, 1]);
+        assert_eq!(hits[1].description_matches, vec![0..4]);
+        assert!(search_tasks(&tasks, "milk laundry").is_empty());
+        assert!(search_tasks(&tasks, "  ").is_empty());
+    }
+}
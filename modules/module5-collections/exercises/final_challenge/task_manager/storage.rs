@@ -0,0 +1,1153 @@
+// Reading and writing task files and databases
+
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time;
+use chrono::NaiveDate;
+use rusqlite::{Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+
+use super::error::TaskError;
+use super::manager::{TaskManager, DEFAULT_PROJECT};
+use super::task::{parse_timestamp, Priority, Recurrence, Task, TaskStatus, TimeEntry, DATE_FORMAT};
+
+// A line of a task file that could not be loaded
+#[derive(Debug, Clone)]
+pub struct SkippedLine {
+    // JSON tasks are read as values without their lines and databases have no lines,
+    // so their problems have no line number
+    pub line: Option<usize>,
+    pub reason: String,
+}
+
+impl fmt::Display for SkippedLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.reason),
+            None => write!(f, "task: {}", self.reason),
+        }
+    }
+}
+
+// What happened while loading a task file
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    pub loaded: usize,
+    pub skipped: Vec<SkippedLine>,
+    // The file's next_id and the value used instead, when the file's would reuse a loaded ID
+    pub next_id_corrected: Option<(u32, u32)>,
+}
+
+impl LoadReport {
+    pub fn is_clean(&self) -> bool {
+        self.skipped.is_empty() && self.next_id_corrected.is_none()
+    }
+
+    // Warnings to show the user, one per line
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<String> = self.skipped.iter()
+            .map(|skipped| format!("Skipped {}", skipped))
+            .collect();
+        if let Some((from, to)) = self.next_id_corrected {
+            warnings.push(format!("next_id {} would reuse a loaded task ID, using {} instead", from, to));
+        }
+        warnings
+    }
+}
+
+// On-disk formats for task files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageFormat {
+    Json,
+    // One `id|title|description|due_date|status|priority|tags|recurrence|parent|depends_on|...`
+    // line per task, after a next_id header
+    Legacy,
+    // An SQLite database, see `SqliteStorage`
+    Sqlite,
+}
+
+// First bytes of every SQLite database file
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+impl StorageFormat {
+    // Pick the format from the file extension: `.json` files use JSON, `.db`, `.sqlite`
+    // and `.sqlite3` files use SQLite
+    pub fn from_path(filename: &str) -> StorageFormat {
+        let extension = Path::new(filename).extension().and_then(|ext| ext.to_str()).map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("json") => StorageFormat::Json,
+            Some("db" | "sqlite" | "sqlite3") => StorageFormat::Sqlite,
+            _ => StorageFormat::Legacy,
+        }
+    }
+
+    // The format of an existing file: SQLite databases are recognized by their header
+    // whatever their name, other files by their extension
+    pub fn of_file(filename: &str) -> StorageFormat {
+        let mut header = [0; SQLITE_HEADER.len()];
+        let is_sqlite = File::open(filename)
+            .and_then(|mut file| file.read_exact(&mut header))
+            .is_ok_and(|()| header == SQLITE_HEADER);
+        if is_sqlite { StorageFormat::Sqlite } else { StorageFormat::from_path(filename) }
+    }
+
+    pub fn parse(name: &str) -> Option<StorageFormat> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(StorageFormat::Json),
+            "pipe" | "legacy" => Some(StorageFormat::Legacy),
+            "sqlite" | "db" => Some(StorageFormat::Sqlite),
+            _ => None,
+        }
+    }
+}
+
+// Layout of a JSON task file; `T` is borrowed when saving and owned when loading.
+// `tasks` belong to the default project, and `project` names the active one.
+#[derive(Serialize, Deserialize)]
+pub struct TaskFile<T> {
+    pub next_id: u32,
+    pub tasks: T,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub projects: BTreeMap<String, T>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+}
+
+// Parse a task file, detecting JSON or the legacy format from its contents. Invalid tasks
+// and duplicate IDs are skipped and reported; next_id is raised past the highest loaded ID.
+pub fn parse_task_file(contents: &str) -> io::Result<(TaskFile<Vec<Task>>, LoadReport)> {
+    let mut report = LoadReport::default();
+    // Each task with the line it was read from, which JSON files do not track
+    let (next_id, tasks): (u32, Vec<(Option<usize>, Task)>);
+    let mut projects = BTreeMap::new();
+    let mut project = None;
+    if contents.trim_start().starts_with('{') {
+        // Tasks are read one at a time, so an invalid one is skipped like an invalid legacy line
+        let file: TaskFile<Vec<serde_json::Value>> = serde_json::from_str(contents)?;
+        next_id = file.next_id;
+        tasks = parse_json_tasks(file.tasks, &mut report).into_iter().map(|task| (None, task)).collect();
+        projects = file.projects.into_iter()
+            .map(|(name, tasks)| (name, parse_json_tasks(tasks, &mut report)))
+            .collect();
+        project = file.project;
+    } else {
+        let mut lines = contents.lines().enumerate();
+        let (_, header) = lines.next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "File is empty"))?;
+        // An invalid header is replaced by the recalculated next_id below
+        let header_id = header.trim().parse::<u32>().unwrap_or_else(|_| {
+            report.skipped.push(SkippedLine {
+                line: Some(1),
+                reason: format!("Invalid next_id header '{}'", header),
+            });
+            0
+        });
+        let mut legacy_tasks = Vec::new();
+        for (index, line) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+            match Task::from_legacy_line(line) {
+                Ok(task) => legacy_tasks.push((Some(index + 1), task)),
+                Err(reason) => report.skipped.push(SkippedLine { line: Some(index + 1), reason }),
+            }
+        }
+        (next_id, tasks) = (header_id, legacy_tasks);
+    }
+
+    // Keep the first task with each ID, across all projects
+    let mut seen = HashSet::new();
+    let mut keep_unique = |task: &Task, line: Option<usize>, report: &mut LoadReport| {
+        let reason = match check_id(task.id) {
+            Err(reason) => reason,
+            Ok(()) if seen.insert(task.id) => return true,
+            Ok(()) => format!("Duplicate task ID #{}", task.id),
+        };
+        report.skipped.push(SkippedLine { line, reason });
+        false
+    };
+    let mut file = TaskFile { next_id, tasks: Vec::new(), projects: BTreeMap::new(), project };
+    for (line, task) in tasks {
+        if keep_unique(&task, line, &mut report) {
+            file.tasks.push(task);
+        }
+    }
+    for (name, mut tasks) in projects {
+        tasks.retain(|task| keep_unique(task, None, &mut report));
+        file.projects.insert(name, tasks);
+    }
+
+    correct_next_id(&mut file, &mut report);
+    Ok((file, report))
+}
+
+// Deserialize the tasks of a JSON file, skipping and reporting the invalid ones by their ID
+fn parse_json_tasks(values: Vec<serde_json::Value>, report: &mut LoadReport) -> Vec<Task> {
+    let mut tasks = Vec::new();
+    for value in values {
+        let id = value.get("id").map(|id| format!("#{}: ", id)).unwrap_or_default();
+        match serde_json::from_value(value) {
+            Ok(task) => tasks.push(task),
+            Err(e) => report.skipped.push(SkippedLine { line: None, reason: format!("{}{}", id, e) }),
+        }
+    }
+    tasks
+}
+
+// Tasks with the highest ID are skipped, since next_id could not move past them
+fn check_id(id: u32) -> Result<(), String> {
+    id.checked_add(1).map(|_| ()).ok_or_else(|| TaskError::IdOutOfRange(id).to_string())
+}
+
+// Raise next_id past the highest loaded ID and count the loaded tasks.
+// A next_id of 0 means it was missing or invalid, which is already reported.
+fn correct_next_id(file: &mut TaskFile<Vec<Task>>, report: &mut LoadReport) {
+    let lowest_free_id = file.tasks.iter()
+        .chain(file.projects.values().flatten())
+        .filter_map(|task| task.id.checked_add(1))
+        .max()
+        .unwrap_or(1);
+    if file.next_id < lowest_free_id {
+        if file.next_id != 0 {
+            report.next_id_corrected = Some((file.next_id, lowest_free_id));
+        }
+        file.next_id = lowest_free_id;
+    }
+    report.loaded = file.tasks.len() + file.projects.values().map(Vec::len).sum::<usize>();
+}
+
+impl Task {
+    // Serialize the task as a line of the legacy pipe-delimited format
+    pub fn to_legacy_line(&self) -> String {
+        let status_str = match self.status {
+            TaskStatus::Pending => "pending",
+            TaskStatus::Completed => "completed",
+        };
+        
+        let due_date_str = match &self.due_date {
+            Some(date) => date.format(DATE_FORMAT).to_string(),
+            None => String::from("none"),
+        };
+        
+        format!("{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}", 
+            self.id, 
+            self.title, 
+            self.description, 
+            due_date_str,
+            status_str,
+            self.priority.to_string().to_lowercase(),
+            self.tags.join(","),
+            self.recurrence.as_ref().map(Recurrence::to_string).unwrap_or_default(),
+            self.parent.map(|parent| parent.to_string()).unwrap_or_default(),
+            self.depends_on.iter().map(u32::to_string).collect::<Vec<_>>().join(","),
+            self.created_at.map(|time| time.to_rfc3339()).unwrap_or_default(),
+            self.completed_at.map(|time| time.to_rfc3339()).unwrap_or_default(),
+            self.time_entries.iter().map(TimeEntry::to_legacy).collect::<Vec<_>>().join(";"),
+            self.snoozed_until.map(|time| time.to_rfc3339()).unwrap_or_default()
+        )
+    }
+
+    // Parse a line of the legacy pipe-delimited format, explaining why when it is invalid.
+    // Older files stop after the status, tags, recurrence, dependency, timestamp, time entry or snooze columns.
+    pub fn from_legacy_line(s: &str) -> Result<Task, String> {
+        let parts: Vec<&str> = s.split('|').collect();
+        if !matches!(parts.len(), 5 | 7 | 8 | 10 | 12 | 13 | 14) {
+            return Err(format!("Expected 5, 7, 8, 10, 12, 13 or 14 '|'-separated fields but found {}", parts.len()));
+        }
+
+        let id = parts[0].parse::<u32>().map_err(|_| format!("Invalid task ID '{}'", parts[0]))?;
+        let title = parts[1].to_string();
+        let description = parts[2].to_string();
+        let due_date = if parts[3] == "none" {
+            None
+        } else {
+            Some(NaiveDate::parse_from_str(parts[3], DATE_FORMAT)
+                .map_err(|_| format!("Invalid due date '{}'", parts[3]))?)
+        };
+        let status = TaskStatus::parse(parts[4]).ok_or_else(|| format!("Unknown status '{}'", parts[4]))?;
+        let (priority, tags) = if parts.len() >= 7 {
+            let priority = Priority::parse(parts[5]).ok_or_else(|| format!("Unknown priority '{}'", parts[5]))?;
+            let tags = parts[6].split(',').filter(|tag| !tag.is_empty()).map(String::from).collect();
+            (priority, tags)
+        } else {
+            (Priority::default(), Vec::new())
+        };
+        let recurrence = match parts.get(7) {
+            Some(spec) if !spec.is_empty() => Some(Recurrence::parse(spec)?),
+            _ => None,
+        };
+        let parse_id = |id: &str| id.parse::<u32>().map_err(|_| format!("Invalid task ID '{}'", id));
+        let parent = match parts.get(8) {
+            Some(id) if !id.is_empty() => Some(parse_id(id)?),
+            _ => None,
+        };
+        let depends_on = match parts.get(9) {
+            Some(ids) if !ids.is_empty() => ids.split(',').map(parse_id).collect::<Result<_, _>>()?,
+            _ => Vec::new(),
+        };
+        let timestamp = |index: usize| match parts.get(index) {
+            Some(time) if !time.is_empty() => parse_timestamp(time).map(Some),
+            _ => Ok(None),
+        };
+        let created_at = timestamp(10)?;
+        let completed_at = timestamp(11)?;
+        let snoozed_until = timestamp(13)?;
+        let time_entries = match parts.get(12) {
+            Some(entries) if !entries.is_empty() => entries.split(';').map(TimeEntry::from_legacy).collect::<Result<_, _>>()?,
+            _ => Vec::new(),
+        };
+
+        Ok(Task {
+            id,
+            title,
+            description,
+            due_date,
+            status,
+            priority,
+            tags,
+            recurrence,
+            parent,
+            depends_on,
+            created_at,
+            completed_at,
+            time_entries,
+            snoozed_until,
+        })
+    }
+}
+
+impl TimeEntry {
+    // Written as `<start>/<end>` in the legacy format, with an empty end while running
+    pub fn to_legacy(&self) -> String {
+        format!("{}/{}", self.start.to_rfc3339(), self.end.map(|end| end.to_rfc3339()).unwrap_or_default())
+    }
+
+    pub fn from_legacy(text: &str) -> Result<TimeEntry, String> {
+        let (start, end) = text.split_once('/').ok_or_else(|| format!("Invalid time entry '{}'", text))?;
+        Ok(TimeEntry {
+            start: parse_timestamp(start)?,
+            end: if end.is_empty() { None } else { Some(parse_timestamp(end)?) },
+        })
+    }
+}
+
+impl TaskFile<&[Task]> {
+    pub fn to_json(&self) -> io::Result<String> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        Ok(json)
+    }
+    
+    pub fn to_legacy(&self) -> io::Result<String> {
+        if self.project.is_some() || !self.projects.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The legacy format cannot hold projects; save as JSON instead",
+            ));
+        }
+        
+        // The legacy format cannot represent these characters, so refuse rather than corrupt the file
+        if let Some(task) = self.tasks.iter().find(|task| {
+            [&task.title, &task.description].iter().any(|field| field.contains(['|', '\n', '\r']))
+        }) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Task #{} contains '|' or a line break; save it as JSON instead", task.id),
+            ));
+        }
+        
+        // Save the next_id at the top of the file, then one task per line
+        let mut contents = format!("{}\n", self.next_id);
+        for task in self.tasks {
+            contents.push_str(&task.to_legacy_line());
+            contents.push('\n');
+        }
+        Ok(contents)
+    }
+}
+
+// Where the TaskManager keeps its tasks between runs
+pub trait Storage {
+    // Read all tasks and projects, or `None` if nothing has been saved yet.
+    // Tasks that cannot be read are skipped and listed in the report.
+    fn load(&self) -> io::Result<Option<(TaskFile<Vec<Task>>, LoadReport)>>;
+    
+    // Replace everything stored with these tasks and projects
+    fn save(&self, file: &TaskFile<&[Task]>) -> io::Result<()>;
+}
+
+// Open the storage for a file: a database for SQLite, otherwise a JSON or legacy task file
+pub fn open_storage(filename: &str, format: StorageFormat) -> io::Result<Box<dyn Storage>> {
+    Ok(match format {
+        StorageFormat::Sqlite => Box::new(SqliteStorage::open(filename)?),
+        format => Box::new(FileStorage { filename: filename.to_string(), format }),
+    })
+}
+
+// A JSON or legacy task file, rewritten as a whole on every save
+pub struct FileStorage {
+    filename: String,
+    format: StorageFormat,
+}
+
+impl Storage for FileStorage {
+    fn load(&self) -> io::Result<Option<(TaskFile<Vec<Task>>, LoadReport)>> {
+        let contents = match fs::read_to_string(&self.filename) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        parse_task_file(&contents).map(Some)
+    }
+    
+    fn save(&self, file: &TaskFile<&[Task]>) -> io::Result<()> {
+        let contents = match self.format {
+            StorageFormat::Legacy => file.to_legacy()?,
+            _ => file.to_json()?,
+        };
+        write_atomically(&self.filename, &contents)
+    }
+}
+
+// Schema changes for SQLite task databases, applied in order. `PRAGMA user_version`
+// records how many of them a database has had, so each runs exactly once.
+const SQLITE_MIGRATIONS: &[&str] = &[
+    // 1: tasks with their tags and dependencies, and settings such as next_id
+    "CREATE TABLE settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE tasks (
+        id INTEGER PRIMARY KEY,
+        position INTEGER NOT NULL,
+        title TEXT NOT NULL,
+        description TEXT NOT NULL,
+        due_date TEXT,
+        status TEXT NOT NULL,
+        priority TEXT NOT NULL,
+        recurrence TEXT,
+        parent INTEGER,
+        created_at TEXT,
+        completed_at TEXT
+    );
+    CREATE TABLE task_tags (
+        task_id INTEGER NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (task_id, position)
+    );
+    CREATE TABLE task_dependencies (
+        task_id INTEGER NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        depends_on INTEGER NOT NULL,
+        PRIMARY KEY (task_id, position)
+    );",
+    // 2: projects; existing tasks belong to the default one
+    "CREATE TABLE projects (
+        name TEXT PRIMARY KEY
+    );
+    INSERT INTO projects (name) VALUES ('default');
+    ALTER TABLE tasks ADD COLUMN project TEXT NOT NULL DEFAULT 'default';
+    CREATE INDEX tasks_by_project ON tasks (project, position);",
+    // 3: time tracked on tasks
+    "CREATE TABLE time_entries (
+        task_id INTEGER NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        started_at TEXT NOT NULL,
+        stopped_at TEXT,
+        PRIMARY KEY (task_id, position)
+    );",
+    // 4: snoozed reminders
+    "ALTER TABLE tasks ADD COLUMN snoozed_until TEXT;",
+];
+
+// Tasks kept in an SQLite database. Every save replaces the stored tasks in a single
+// transaction, so other processes using the same database never see half a save, and
+// fails rather than overwrite changes another process saved since this one loaded.
+pub struct SqliteStorage {
+    connection: Connection,
+    // The database's revision when it was last loaded or saved; saves bump it by one
+    revision: Cell<Option<u64>>,
+}
+
+impl SqliteStorage {
+    pub fn open(filename: &str) -> io::Result<SqliteStorage> {
+        let connection = Connection::open(filename).map_err(io::Error::other)?;
+        // Wait for other processes to finish writing instead of failing straight away
+        connection.busy_timeout(std::time::Duration::from_secs(5)).map_err(io::Error::other)?;
+        SqliteStorage::with_connection(connection)
+    }
+    
+    #[cfg(test)]
+    pub fn open_in_memory() -> io::Result<SqliteStorage> {
+        SqliteStorage::with_connection(Connection::open_in_memory().map_err(io::Error::other)?)
+    }
+    
+    fn with_connection(connection: Connection) -> io::Result<SqliteStorage> {
+        connection.pragma_update(None, "foreign_keys", true).map_err(io::Error::other)?;
+        migrate_database(&connection)?;
+        Ok(SqliteStorage { connection, revision: Cell::new(None) })
+    }
+    
+    fn setting(connection: &Connection, key: &str) -> rusqlite::Result<Option<String>> {
+        connection
+            .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+            .optional()
+    }
+    
+    // Databases saved before revisions were tracked count as revision 0
+    fn current_revision(connection: &Connection) -> rusqlite::Result<u64> {
+        let revision = SqliteStorage::setting(connection, "revision")?;
+        Ok(revision.and_then(|revision| revision.parse().ok()).unwrap_or(0))
+    }
+    
+    fn read(&self) -> rusqlite::Result<Option<(TaskFile<Vec<Task>>, LoadReport)>> {
+        // Read everything within one transaction so a concurrent save is seen entirely or not at all
+        let transaction = self.connection.unchecked_transaction()?;
+        self.revision.set(Some(SqliteStorage::current_revision(&transaction)?));
+        let setting = |key: &str| SqliteStorage::setting(&transaction, key);
+        let Some(next_id) = setting("next_id")? else {
+            return Ok(None);
+        };
+        let mut report = LoadReport::default();
+        // An invalid next_id is replaced by the recalculated one
+        let next_id = next_id.parse::<u32>().unwrap_or_else(|_| {
+            report.skipped.push(SkippedLine { line: None, reason: format!("Invalid next_id '{}'", next_id) });
+            0
+        });
+        let mut file = TaskFile { next_id, tasks: Vec::new(), projects: BTreeMap::new(), project: setting("project")? };
+        
+        let mut statement = transaction.prepare("SELECT name FROM projects WHERE name != ?1")?;
+        for name in statement.query_map([DEFAULT_PROJECT], |row| row.get::<_, String>(0))? {
+            file.projects.insert(name?, Vec::new());
+        }
+        
+        let mut tags: HashMap<u32, Vec<String>> = HashMap::new();
+        let mut statement = transaction.prepare("SELECT task_id, tag FROM task_tags ORDER BY task_id, position")?;
+        for row in statement.query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?)))? {
+            let (id, tag) = row?;
+            tags.entry(id).or_default().push(tag);
+        }
+        let mut depends_on: HashMap<u32, Vec<u32>> = HashMap::new();
+        let mut statement = transaction
+            .prepare("SELECT task_id, depends_on FROM task_dependencies ORDER BY task_id, position")?;
+        for row in statement.query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, u32>(1)?)))? {
+            let (id, dependency) = row?;
+            depends_on.entry(id).or_default().push(dependency);
+        }
+        let mut time_entries: HashMap<u32, Vec<(String, Option<String>)>> = HashMap::new();
+        let mut statement = transaction
+            .prepare("SELECT task_id, started_at, stopped_at FROM time_entries ORDER BY task_id, position")?;
+        for row in statement.query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get(1)?, row.get(2)?)))? {
+            let (id, start, end) = row?;
+            time_entries.entry(id).or_default().push((start, end));
+        }
+        
+        let mut statement = transaction.prepare(
+            "SELECT id, project, title, description, due_date, status, priority, recurrence, parent,
+                    created_at, completed_at, snoozed_until
+             FROM tasks ORDER BY project, position",
+        )?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let id: u32 = row.get("id")?;
+            let project: String = row.get("project")?;
+            let text = |column: &str| row.get::<_, Option<String>>(column);
+            let (due_date, status, priority) = (text("due_date")?, text("status")?, text("priority")?);
+            let (recurrence, created_at, completed_at) = (text("recurrence")?, text("created_at")?, text("completed_at")?);
+            let snoozed_until = text("snoozed_until")?;
+            let task = (|| {
+                check_id(id)?;
+                Ok::<_, String>(Task {
+                    id,
+                    title: row.get("title").map_err(|e| e.to_string())?,
+                    description: row.get("description").map_err(|e| e.to_string())?,
+                    due_date: due_date.as_deref()
+                        .map(|date| NaiveDate::parse_from_str(date, DATE_FORMAT)
+                            .map_err(|_| format!("Invalid due date '{}'", date)))
+                        .transpose()?,
+                    status: status.as_deref().and_then(TaskStatus::parse)
+                        .ok_or_else(|| format!("Unknown status '{}'", status.as_deref().unwrap_or_default()))?,
+                    priority: priority.as_deref().and_then(Priority::parse)
+                        .ok_or_else(|| format!("Unknown priority '{}'", priority.as_deref().unwrap_or_default()))?,
+                    tags: tags.remove(&id).unwrap_or_default(),
+                    recurrence: recurrence.as_deref().map(Recurrence::parse).transpose()?,
+                    parent: row.get("parent").map_err(|e| e.to_string())?,
+                    depends_on: depends_on.remove(&id).unwrap_or_default(),
+                    created_at: created_at.as_deref().map(parse_timestamp).transpose()?,
+                    completed_at: completed_at.as_deref().map(parse_timestamp).transpose()?,
+                    time_entries: time_entries.remove(&id).unwrap_or_default().iter()
+                        .map(|(start, end)| Ok(TimeEntry {
+                            start: parse_timestamp(start)?,
+                            end: end.as_deref().map(parse_timestamp).transpose()?,
+                        }))
+                        .collect::<Result<_, String>>()?,
+                    snoozed_until: snoozed_until.as_deref().map(parse_timestamp).transpose()?,
+                })
+            })();
+            match task {
+                Ok(task) if project == DEFAULT_PROJECT => file.tasks.push(task),
+                Ok(task) => file.projects.entry(project).or_default().push(task),
+                Err(reason) => report.skipped.push(SkippedLine { line: None, reason: format!("#{}: {}", id, reason) }),
+            }
+        }
+        
+        correct_next_id(&mut file, &mut report);
+        Ok(Some((file, report)))
+    }
+    
+    // Replace the stored tasks, unless another process saved since they were last loaded,
+    // in which case nothing is written and `false` is returned
+    fn write(&self, file: &TaskFile<&[Task]>) -> rusqlite::Result<bool> {
+        // Take the write lock straight away so the revision cannot change before the commit
+        let transaction = Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)?;
+        let revision = SqliteStorage::current_revision(&transaction)?;
+        if self.revision.get().is_some_and(|loaded| loaded != revision) {
+            return Ok(false);
+        }
+        transaction.execute_batch(
+            "DELETE FROM time_entries;
+             DELETE FROM task_dependencies;
+             DELETE FROM task_tags;
+             DELETE FROM tasks;
+             DELETE FROM projects;",
+        )?;
+        {
+            let mut insert_setting = transaction.prepare("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)")?;
+            insert_setting.execute(("next_id", file.next_id.to_string()))?;
+            insert_setting.execute(("revision", (revision + 1).to_string()))?;
+            match &file.project {
+                Some(project) => insert_setting.execute(("project", project))?,
+                None => transaction.execute("DELETE FROM settings WHERE key = 'project'", [])?,
+            };
+            
+            let mut insert_project = transaction.prepare("INSERT INTO projects (name) VALUES (?1)")?;
+            let mut insert_task = transaction.prepare(
+                "INSERT INTO tasks (id, project, position, title, description, due_date, status, priority,
+                                    recurrence, parent, created_at, completed_at, snoozed_until)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            )?;
+            let mut insert_tag = transaction.prepare("INSERT INTO task_tags (task_id, position, tag) VALUES (?1, ?2, ?3)")?;
+            let mut insert_dependency = transaction
+                .prepare("INSERT INTO task_dependencies (task_id, position, depends_on) VALUES (?1, ?2, ?3)")?;
+            let mut insert_time_entry = transaction.prepare(
+                "INSERT INTO time_entries (task_id, position, started_at, stopped_at) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            let projects = std::iter::once((DEFAULT_PROJECT, file.tasks))
+                .chain(file.projects.iter().map(|(name, tasks)| (name.as_str(), *tasks)));
+            for (project, tasks) in projects {
+                insert_project.execute([project])?;
+                for (position, task) in tasks.iter().enumerate() {
+                    insert_task.execute(rusqlite::params![
+                        task.id,
+                        project,
+                        position,
+                        task.title,
+                        task.description,
+                        task.due_date.map(|date| date.format(DATE_FORMAT).to_string()),
+                        task.status.to_string().to_lowercase(),
+                        task.priority.to_string().to_lowercase(),
+                        task.recurrence.as_ref().map(Recurrence::to_string),
+                        task.parent,
+                        task.created_at.map(|time| time.to_rfc3339()),
+                        task.completed_at.map(|time| time.to_rfc3339()),
+                        task.snoozed_until.map(|time| time.to_rfc3339()),
+                    ])?;
+                    for (position, tag) in task.tags.iter().enumerate() {
+                        insert_tag.execute((task.id, position, tag))?;
+                    }
+                    for (position, dependency) in task.depends_on.iter().enumerate() {
+                        insert_dependency.execute((task.id, position, dependency))?;
+                    }
+                    for (position, entry) in task.time_entries.iter().enumerate() {
+                        let end = entry.end.map(|end| end.to_rfc3339());
+                        insert_time_entry.execute((task.id, position, entry.start.to_rfc3339(), end))?;
+                    }
+                }
+            }
+        }
+        transaction.commit()?;
+        self.revision.set(Some(revision + 1));
+        Ok(true)
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load(&self) -> io::Result<Option<(TaskFile<Vec<Task>>, LoadReport)>> {
+        self.read().map_err(io::Error::other)
+    }
+    
+    fn save(&self, file: &TaskFile<&[Task]>) -> io::Result<()> {
+        if self.write(file).map_err(io::Error::other)? {
+            Ok(())
+        } else {
+            Err(io::Error::other("Another process changed the database since it was loaded; load it again"))
+        }
+    }
+}
+
+// Bring a database's schema up to date by applying the migrations it has not had yet
+fn migrate_database(connection: &Connection) -> io::Result<()> {
+    loop {
+        // Each migration and its version bump succeed or fail together, and the write lock
+        // keeps processes opening the database at the same time from both applying it
+        let transaction = Transaction::new_unchecked(connection, TransactionBehavior::Immediate)
+            .map_err(io::Error::other)?;
+        let version: usize = transaction
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .map_err(io::Error::other)?;
+        if version > SQLITE_MIGRATIONS.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("The database uses schema version {}, newer than this program supports", version),
+            ));
+        }
+        let Some(migration) = SQLITE_MIGRATIONS.get(version) else {
+            return Ok(());
+        };
+        let result = (|| {
+            transaction.execute_batch(migration)?;
+            transaction.pragma_update(None, "user_version", version + 1)?;
+            transaction.commit()
+        })();
+        result.map_err(|e| io::Error::other(format!("Migrating the database to version {} failed: {}", version + 1, e)))?;
+    }
+}
+
+// Flush a directory entry change such as a rename to disk. Windows cannot open directories
+// and commits renames without it.
+fn sync_parent(path: &Path) -> io::Result<()> {
+    if cfg!(unix) {
+        let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+// `<path>.<suffix>`, e.g. `tasks.json.bak`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// Temporary files written by this process so far, to give each one its own name
+static TEMP_FILES: AtomicU64 = AtomicU64::new(0);
+
+// Temporary files older than this were left behind by a crash rather than by a save in progress
+const STALE_TEMP_FILE_AGE: time::Duration = time::Duration::from_secs(10 * 60);
+
+// Create a new temporary file next to `path`, named `<path>.<pid>-<counter>.tmp`. A name
+// taken by a file that an earlier process with the same ID left behind is skipped.
+fn create_temp_file(path: &Path) -> io::Result<(File, PathBuf)> {
+    loop {
+        let counter = TEMP_FILES.fetch_add(1, Ordering::Relaxed);
+        let temp_path = with_suffix(path, &format!("{}-{}.tmp", process::id(), counter));
+        match OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+            Ok(file) => return Ok((file, temp_path)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Delete the temporary files of `path` that crashed saves left behind. This is best effort:
+// a file that cannot be read or removed is left for the next save.
+fn remove_stale_temp_files(path: &Path) {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return;
+    };
+    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let Ok(entries) = fs::read_dir(parent) else {
+        return;
+    };
+    let is_number = |text: &str| !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit());
+    for entry in entries.flatten() {
+        let is_temp_file = entry.file_name().to_str()
+            .and_then(|file_name| file_name.strip_prefix(name)?.strip_prefix('.')?.strip_suffix(".tmp"))
+            .and_then(|suffix| suffix.split_once('-'))
+            .is_some_and(|(pid, counter)| is_number(pid) && is_number(counter));
+        let is_stale = entry.metadata()
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age > STALE_TEMP_FILE_AGE));
+        if is_temp_file && is_stale {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+// Replace a file's contents without ever leaving it partially written: the data is
+// written and flushed to a temporary file that is then renamed over the target.
+// Each save creates its own temporary file, so saves from two processes cannot write
+// into each other's, and ones left behind by crashes are deleted once they are stale.
+// The previous version of the file is kept as `<filename>.bak`.
+pub fn write_atomically(filename: &str, contents: &str) -> io::Result<()> {
+    let path = Path::new(filename);
+    remove_stale_temp_files(path);
+    let (mut file, temp_path) = create_temp_file(path)?;
+    
+    let result = (|| {
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        
+        if path.exists() {
+            fs::copy(path, with_suffix(path, "bak"))?;
+        }
+        fs::rename(&temp_path, path)?;
+        sync_parent(path)
+    })();
+    
+    // Once renamed, the temporary file is the target itself
+    if result.is_err() && temp_path.exists() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+// Convert a task file (usually in the legacy format) to JSON, or to SQLite when the destination
+// is a `.db`, `.sqlite` or `.sqlite3` file, leaving the current tasks untouched. Returns the report of loading the source file.
+pub fn migrate_file(source: &str, destination: &str, strict: bool) -> io::Result<LoadReport> {
+    if source == destination {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Destination must differ from the source file",
+        ));
+    }
+    
+    let mut migrated = TaskManager::new();
+    let report = migrated.load_from_file(source, strict)?;
+    let format = match StorageFormat::from_path(destination) {
+        StorageFormat::Sqlite => StorageFormat::Sqlite,
+        _ => StorageFormat::Json,
+    };
+    migrated.save_to_file(destination, format)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_manager::testing::{self, task};
+    use chrono::{Duration, Local, Weekday};
+    use proptest::prelude::*;
+
+    // The tasks of the default project only, as files without projects hold them
+    fn task_file(next_id: u32, tasks: &[Task]) -> TaskFile<&[Task]> {
+        TaskFile { next_id, tasks, projects: BTreeMap::new(), project: None }
+    }
+
+    #[test]
+    fn sqlite_storage_round_trips_tasks_and_projects() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        assert!(storage.load().unwrap().is_none());
+
+        let mut report = task(1, "Weekly report");
+        report.description = "Sales | costs\nand forecasts".to_string();
+        report.due_date = NaiveDate::from_ymd_opt(2026, 10, 26);
+        report.priority = Priority::High;
+        report.tags = vec!["work".to_string(), "finance".to_string()];
+        report.recurrence = Some(Recurrence::Weekly { weekdays: vec![Weekday::Mon] });
+        report.depends_on = vec![3];
+        report.created_at = Some(Local::now());
+        report.time_entries = vec![
+            TimeEntry { start: Local::now() - Duration::hours(2), end: Some(Local::now() - Duration::hours(1)) },
+            TimeEntry { start: Local::now(), end: None },
+        ];
+        let mut subtask = task(2, "Collect numbers");
+        subtask.parent = Some(1);
+        subtask.status = TaskStatus::Completed;
+        subtask.completed_at = Some(Local::now());
+        let tasks = vec![report, subtask];
+        let travel = vec![task(3, "Book flights")];
+
+        let mut file = task_file(4, &tasks);
+        file.projects.insert("travel".to_string(), &travel);
+        file.projects.insert("empty".to_string(), &[]);
+        file.project = Some("travel".to_string());
+        storage.save(&file).unwrap();
+
+        let (loaded, load_report) = storage.load().unwrap().unwrap();
+        assert!(load_report.is_clean());
+        assert_eq!(load_report.loaded, 3);
+        assert_eq!(loaded.next_id, 4);
+        assert_eq!(loaded.tasks, tasks);
+        assert_eq!(loaded.projects["travel"], travel);
+        assert!(loaded.projects["empty"].is_empty());
+        assert_eq!(loaded.project.as_deref(), Some("travel"));
+    }
+
+    #[test]
+    fn sqlite_storage_saves_replace_previous_contents() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        storage.save(&task_file(3, &[task(1, "First"), task(2, "Second")])).unwrap();
+
+        let mut file = task_file(3, &[]);
+        let home = [task(2, "Second")];
+        file.projects.insert("home".to_string(), &home);
+        storage.save(&file).unwrap();
+
+        let (loaded, _) = storage.load().unwrap().unwrap();
+        assert!(loaded.tasks.is_empty());
+        assert_eq!(loaded.projects.len(), 1);
+        assert_eq!(loaded.projects["home"], home);
+        assert_eq!(loaded.project, None);
+    }
+
+    #[test]
+    fn sqlite_storage_refuses_to_overwrite_changes_from_another_process() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        let tasks = [task(1, "First")];
+        storage.save(&task_file(2, &tasks)).unwrap();
+        storage.load().unwrap().unwrap();
+
+        // Another process saving bumps the revision
+        storage.connection.execute("UPDATE settings SET value = '5' WHERE key = 'revision'", []).unwrap();
+        assert!(storage.save(&task_file(2, &tasks)).is_err());
+
+        // Loading again picks up the other process's changes, after which saving works
+        storage.load().unwrap().unwrap();
+        storage.save(&task_file(2, &tasks)).unwrap();
+    }
+
+    #[test]
+    fn migrations_bring_databases_up_to_date_once() {
+        let connection = Connection::open_in_memory().unwrap();
+        migrate_database(&connection).unwrap();
+        migrate_database(&connection).unwrap();
+        let version: usize = connection.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap();
+        assert_eq!(version, SQLITE_MIGRATIONS.len());
+    }
+
+    #[test]
+    fn migrations_keep_tasks_written_before_projects() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(SQLITE_MIGRATIONS[0]).unwrap();
+        connection.pragma_update(None, "user_version", 1).unwrap();
+        connection.execute_batch(
+            "INSERT INTO settings (key, value) VALUES ('next_id', '2');
+             INSERT INTO tasks (id, position, title, description, status, priority)
+             VALUES (1, 0, 'Old task', 'From version 1', 'pending', 'low');",
+        ).unwrap();
+
+        let storage = SqliteStorage::with_connection(connection).unwrap();
+        let (file, report) = storage.load().unwrap().unwrap();
+        assert!(report.is_clean());
+        assert!(file.projects.is_empty());
+        assert_eq!(file.tasks.len(), 1);
+        assert_eq!(file.tasks[0].title, "Old task");
+        assert_eq!(file.tasks[0].priority, Priority::Low);
+    }
+
+    #[test]
+    fn newer_schema_versions_are_refused() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.pragma_update(None, "user_version", SQLITE_MIGRATIONS.len() + 1).unwrap();
+        assert!(SqliteStorage::with_connection(connection).is_err());
+    }
+
+    #[test]
+    fn invalid_database_rows_are_skipped_and_reported() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        storage.save(&task_file(3, &[task(1, "Good"), task(2, "Bad")])).unwrap();
+        storage.connection.execute("UPDATE tasks SET status = 'done' WHERE id = 2", []).unwrap();
+
+        let (file, report) = storage.load().unwrap().unwrap();
+        assert_eq!(file.tasks, vec![task(1, "Good")]);
+        assert_eq!(report.loaded, 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert!(report.skipped[0].reason.contains("Unknown status 'done'"));
+    }
+
+    #[test]
+    fn json_files_skip_invalid_tasks_and_keep_the_rest() {
+        let json = |id: u32, field: &str, value: serde_json::Value| {
+            let mut task = serde_json::to_value(task(id, "Task")).unwrap();
+            task[field] = value;
+            task
+        };
+        let contents = serde_json::json!({
+            "next_id": 5,
+            "tasks": [json(1, "title", "Good".into()), json(2, "due_date", "someday".into()), json(3, "status", "done".into())],
+            "projects": { "work": [json(4, "title", "Also good".into()), serde_json::json!({ "title": "No ID" })] },
+        });
+
+        let (file, report) = parse_task_file(&contents.to_string()).unwrap();
+        assert_eq!(file.tasks.iter().map(|task| task.id).collect::<Vec<_>>(), [1]);
+        assert_eq!(file.projects["work"].iter().map(|task| task.id).collect::<Vec<_>>(), [4]);
+        assert_eq!(report.loaded, 2);
+        let reasons: Vec<&str> = report.skipped.iter().map(|skipped| skipped.reason.as_str()).collect();
+        assert_eq!(reasons.len(), 3);
+        assert!(reasons[0].starts_with("#2: "), "{}", reasons[0]);
+        assert!(reasons[1].starts_with("#3: "), "{}", reasons[1]);
+        assert!(reasons[2].contains("missing field `id`"), "{}", reasons[2]);
+        assert!(parse_task_file(r#"{"next_id": "five", "tasks": []}"#).is_err());
+    }
+
+    #[test]
+    fn legacy_files_skip_invalid_lines_and_duplicates() {
+        let contents = "2\n\
+            1|Buy milk|Oat milk|2026-10-20|pending\n\
+            2|Broken|line\n\
+            3|Call mom|Sunday|none|completed|high|family\n\
+            3|Again|Duplicate|none|pending\n";
+        let (file, report) = parse_task_file(contents).unwrap();
+        let titles: Vec<&str> = file.tasks.iter().map(|task| task.title.as_str()).collect();
+        assert_eq!(titles, ["Buy milk", "Call mom"]);
+        assert_eq!(file.tasks[1].priority, Priority::High);
+        assert_eq!(file.tasks[1].tags, ["family"]);
+        assert_eq!(report.loaded, 2);
+        let lines: Vec<Option<usize>> = report.skipped.iter().map(|skipped| skipped.line).collect();
+        assert_eq!(lines, [Some(3), Some(5)]);
+        assert_eq!(file.next_id, 4);
+        assert_eq!(report.next_id_corrected, Some((2, 4)));
+    }
+
+    #[test]
+    fn the_highest_task_id_is_skipped_rather_than_overflowing() {
+        let contents = "4294967295\n\
+            1|Buy milk|Oat milk|none|pending\n\
+            4294967295|Last|No ID after it|none|pending\n";
+        let (file, report) = parse_task_file(contents).unwrap();
+        let ids: Vec<u32> = file.tasks.iter().map(|task| task.id).collect();
+        assert_eq!(ids, [1]);
+        assert_eq!(file.next_id, u32::MAX);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].line, Some(3));
+        assert!(report.skipped[0].reason.contains("#4294967295 is out of range"));
+
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        storage.save(&task_file(3, &[task(1, "Good"), task(2, "Last")])).unwrap();
+        storage.connection.execute("UPDATE tasks SET id = 4294967295 WHERE id = 2", []).unwrap();
+        let (file, report) = storage.load().unwrap().unwrap();
+        assert_eq!(file.tasks, vec![task(1, "Good")]);
+        assert!(report.skipped[0].reason.contains("#4294967295 is out of range"));
+    }
+
+    #[test]
+    fn empty_files_are_an_error() {
+        assert!(parse_task_file("").is_err());
+        assert!(parse_task_file("{ not json").is_err());
+    }
+
+    #[test]
+    fn atomic_writes_keep_a_backup_and_leave_no_temporary_files() {
+        let dir = testing::TempDir::new("atomic-writes");
+        let file = dir.file("tasks.json");
+        // Temporary files that an earlier process with our ID left behind under the next names
+        // this one will try are skipped, and one left long enough to be stale is removed
+        let next = TEMP_FILES.load(Ordering::Relaxed);
+        let mut leftovers: Vec<String> = (next..next + 8)
+            .map(|counter| format!("tasks.json.{}-{}.tmp", process::id(), counter))
+            .collect();
+        for name in &leftovers {
+            fs::write(dir.file(name), "half written").unwrap();
+        }
+        let stale = dir.file("tasks.json.1-1.tmp");
+        fs::write(&stale, "half written").unwrap();
+        let an_hour_ago = time::SystemTime::now() - time::Duration::from_secs(60 * 60);
+        File::options().write(true).open(&stale).unwrap().set_modified(an_hour_ago).unwrap();
+
+        write_atomically(&file, "first").unwrap();
+        write_atomically(&file, "second").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "second");
+        assert_eq!(fs::read_to_string(format!("{}.bak", file)).unwrap(), "first");
+        assert!(!Path::new(&stale).exists());
+
+        let mut names: Vec<String> = fs::read_dir(Path::new(&file).parent().unwrap()).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        leftovers.extend(["tasks.json".to_string(), "tasks.json.bak".to_string()]);
+        leftovers.sort();
+        assert_eq!(names, leftovers);
+    }
+
+    #[test]
+    fn legacy_format_refuses_what_it_cannot_hold() {
+        let mut piped = task(1, "Milk");
+        piped.title = "Milk | eggs".to_string();
+        assert!(task_file(2, &[piped]).to_legacy().is_err());
+
+        let tasks = [task(1, "Milk")];
+        let mut file = task_file(2, &tasks);
+        assert!(file.to_legacy().is_ok());
+        file.project = Some("home".to_string());
+        assert!(file.to_legacy().is_err());
+    }
+
+    #[test]
+    fn formats_are_recognized_by_extension() {
+        assert_eq!(StorageFormat::from_path("tasks.JSON"), StorageFormat::Json);
+        assert_eq!(StorageFormat::from_path("tasks.db"), StorageFormat::Sqlite);
+        assert_eq!(StorageFormat::from_path("tasks.txt"), StorageFormat::Legacy);
+        assert_eq!(StorageFormat::parse("pipe"), Some(StorageFormat::Legacy));
+        assert_eq!(StorageFormat::parse("xml"), None);
+    }
+
+    proptest! {
+        #[test]
+        fn json_files_round_trip(tasks in testing::tasks(), project_tasks in testing::tasks()) {
+            // Tasks of the other project follow on from the default project's IDs
+            let offset = tasks.len() as u32;
+            let project_tasks: Vec<Task> = project_tasks.into_iter()
+                .map(|task| Task { id: task.id + offset, ..task })
+                .collect();
+            let next_id = offset + project_tasks.len() as u32 + 1;
+            let mut file = task_file(next_id, &tasks);
+            file.projects.insert("work".to_string(), &project_tasks);
+            file.project = Some("work".to_string());
+
+            let (loaded, report) = parse_task_file(&file.to_json().unwrap()).unwrap();
+            prop_assert!(report.is_clean());
+            prop_assert_eq!(loaded.next_id, next_id);
+            prop_assert_eq!(&loaded.tasks, &tasks);
+            prop_assert_eq!(&loaded.projects["work"], &project_tasks);
+            prop_assert_eq!(loaded.project, file.project);
+        }
+
+        #[test]
+        fn legacy_files_round_trip(tasks in testing::tasks()) {
+            let next_id = tasks.len() as u32 + 1;
+            let contents = task_file(next_id, &tasks).to_legacy().unwrap();
+            let (loaded, report) = parse_task_file(&contents).unwrap();
+            prop_assert!(report.is_clean());
+            prop_assert_eq!(loaded.next_id, next_id);
+            prop_assert_eq!(loaded.tasks, tasks);
+        }
+
+        #[test]
+        fn legacy_lines_round_trip(tasks in testing::tasks()) {
+            for task in tasks {
+                prop_assert_eq!(Task::from_legacy_line(&task.to_legacy_line()), Ok(task));
+            }
+        }
+    }
+
+    proptest! {
+        // Every case opens a database, so fewer of them
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn sqlite_databases_round_trip(tasks in testing::tasks(), project_tasks in testing::tasks()) {
+            let offset = tasks.len() as u32;
+            let project_tasks: Vec<Task> = project_tasks.into_iter()
+                .map(|task| Task { id: task.id + offset, ..task })
+                .collect();
+            let next_id = offset + project_tasks.len() as u32 + 1;
+            let mut file = task_file(next_id, &tasks);
+            file.projects.insert("work".to_string(), &project_tasks);
+
+            let storage = SqliteStorage::open_in_memory().unwrap();
+            storage.save(&file).unwrap();
+            let (loaded, report) = storage.load().unwrap().unwrap();
+            prop_assert!(report.is_clean());
+            prop_assert_eq!(loaded.next_id, next_id);
+            prop_assert_eq!(&loaded.tasks, &tasks);
+            prop_assert_eq!(&loaded.projects["work"], &project_tasks);
+        }
+    }
+}
@@ -0,0 +1,524 @@
+// Tasks and the values they are made of, with parsing of dates, spans and tags
+
+use std::fmt;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+use super::error::TaskError;
+
+// Format used for due dates in files and output
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// Today's date in the local timezone
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+// Format used for creation and completion times in output
+pub const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+// Parse a length of time such as `30m`, `2h`, `3d` or `1w`
+pub fn parse_span(input: &str) -> Result<Duration, TaskError> {
+    let input = input.trim().to_lowercase();
+    let (amount, to_span): (&str, fn(i64) -> Option<Duration>) = if let Some(amount) = input.strip_suffix('m') {
+        (amount, Duration::try_minutes)
+    } else if let Some(amount) = input.strip_suffix('h') {
+        (amount, Duration::try_hours)
+    } else if let Some(amount) = input.strip_suffix('d') {
+        (amount, Duration::try_days)
+    } else if let Some(amount) = input.strip_suffix('w') {
+        (amount, Duration::try_weeks)
+    } else {
+        return Err(TaskError::InvalidSpan(input));
+    };
+    match amount.parse::<i64>() {
+        Ok(amount) if amount >= 0 => to_span(amount).ok_or(TaskError::SpanOutOfRange(input)),
+        _ => Err(TaskError::InvalidSpan(input)),
+    }
+}
+
+// Parse a due date relative to `today`.
+// Accepts ISO dates (2026-10-20), `today`, `tomorrow`, and offsets like `+3d` or `+2w`.
+pub fn parse_due_date(input: &str, today: NaiveDate) -> Result<NaiveDate, TaskError> {
+    let input = input.trim().to_lowercase();
+    match input.as_str() {
+        "today" => return Ok(today),
+        "tomorrow" => return today.succ_opt().ok_or(TaskError::DateOutOfRange(input)),
+        _ => {}
+    }
+
+    if let Some(offset) = input.strip_prefix('+') {
+        let (amount, days_per_unit) = if let Some(amount) = offset.strip_suffix('d') {
+            (amount, 1)
+        } else if let Some(amount) = offset.strip_suffix('w') {
+            (amount, 7)
+        } else {
+            return Err(TaskError::InvalidRelativeDate(input));
+        };
+        let Ok(amount) = amount.parse::<i64>() else {
+            return Err(TaskError::InvalidRelativeDate(input));
+        };
+        return amount.checked_mul(days_per_unit)
+            .and_then(Duration::try_days)
+            .and_then(|offset| today.checked_add_signed(offset))
+            .ok_or(TaskError::DateOutOfRange(input));
+    }
+
+    NaiveDate::parse_from_str(&input, DATE_FORMAT).map_err(|_| TaskError::InvalidDate(input))
+}
+
+// Task status enum
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Pending,
+    Completed,
+}
+
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TaskStatus::Pending => write!(f, "Pending"),
+            TaskStatus::Completed => write!(f, "Completed"),
+        }
+    }
+}
+
+impl TaskStatus {
+    pub fn parse(name: &str) -> Option<TaskStatus> {
+        match name.to_lowercase().as_str() {
+            "pending" => Some(TaskStatus::Pending),
+            "completed" => Some(TaskStatus::Completed),
+            _ => None,
+        }
+    }
+}
+
+// Task priority levels, ordered from lowest to highest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Urgent,
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Priority::Low => write!(f, "Low"),
+            Priority::Medium => write!(f, "Medium"),
+            Priority::High => write!(f, "High"),
+            Priority::Urgent => write!(f, "Urgent"),
+        }
+    }
+}
+
+impl Priority {
+    pub fn parse(name: &str) -> Option<Priority> {
+        match name.to_lowercase().as_str() {
+            "low" => Some(Priority::Low),
+            "medium" => Some(Priority::Medium),
+            "high" => Some(Priority::High),
+            "urgent" => Some(Priority::Urgent),
+            _ => None,
+        }
+    }
+}
+
+// How often a recurring task repeats. Written `daily`, `weekly`, `weekly:mon,thu`,
+// `monthly:15` or `every:3d`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum Recurrence {
+    Daily,
+    // An empty list repeats on the same weekday as the current due date
+    Weekly { weekdays: Vec<Weekday> },
+    // Days past the end of a short month fall on its last day
+    Monthly { day: u32 },
+    EveryNDays { days: u32 },
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly { weekdays } if weekdays.is_empty() => write!(f, "weekly"),
+            Recurrence::Weekly { weekdays } => {
+                let names: Vec<String> = weekdays.iter().map(|day| day.to_string().to_lowercase()).collect();
+                write!(f, "weekly:{}", names.join(","))
+            },
+            Recurrence::Monthly { day } => write!(f, "monthly:{}", day),
+            Recurrence::EveryNDays { days } => write!(f, "every:{}d", days),
+        }
+    }
+}
+
+impl Recurrence {
+    pub fn parse(input: &str) -> Result<Recurrence, String> {
+        let input = input.trim().to_lowercase();
+        let (kind, value) = match input.split_once(':') {
+            Some((kind, value)) => (kind, Some(value)),
+            None => (input.as_str(), None),
+        };
+
+        match (kind, value) {
+            ("daily", None) => Ok(Recurrence::Daily),
+            ("weekly", None) => Ok(Recurrence::Weekly { weekdays: Vec::new() }),
+            ("weekly", Some(days)) => {
+                let mut weekdays = Vec::new();
+                for name in days.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+                    let day = name.parse::<Weekday>()
+                        .map_err(|_| format!("Unknown weekday '{}', expected e.g. mon or thu", name))?;
+                    if !weekdays.contains(&day) {
+                        weekdays.push(day);
+                    }
+                }
+                if weekdays.is_empty() {
+                    return Err("'weekly:' requires at least one weekday, e.g. weekly:mon,thu".to_string());
+                }
+                weekdays.sort_by_key(|day| day.num_days_from_monday());
+                Ok(Recurrence::Weekly { weekdays })
+            },
+            ("monthly", Some(day)) => match day.parse::<u32>() {
+                Ok(day @ 1..=31) => Ok(Recurrence::Monthly { day }),
+                _ => Err(format!("Invalid day of month '{}', expected 1 to 31", day)),
+            },
+            ("every", Some(days)) => match days.strip_suffix('d').unwrap_or(days).parse::<u32>() {
+                Ok(days) if days > 0 => Ok(Recurrence::EveryNDays { days }),
+                _ => Err(format!("Invalid interval '{}', expected e.g. every:3d", days)),
+            },
+            _ => Err(format!(
+                "Unknown recurrence '{}', expected daily, weekly[:mon,thu], monthly:N or every:Nd",
+                input
+            )),
+        }
+    }
+
+    // Human-readable form for task details
+    pub fn describe(&self) -> String {
+        match self {
+            Recurrence::Daily => "every day".to_string(),
+            Recurrence::Weekly { weekdays } if weekdays.is_empty() => "every week".to_string(),
+            Recurrence::Weekly { weekdays } => {
+                let names: Vec<String> = weekdays.iter().map(Weekday::to_string).collect();
+                format!("every week on {}", names.join(", "))
+            },
+            Recurrence::Monthly { day } => format!("every month on day {}", day),
+            Recurrence::EveryNDays { days: 1 } => "every day".to_string(),
+            Recurrence::EveryNDays { days } => format!("every {} days", days),
+        }
+    }
+
+    // The first occurrence strictly after `date`
+    pub fn next_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Recurrence::Daily => date.succ_opt(),
+            Recurrence::EveryNDays { days } => date.checked_add_signed(Duration::days(i64::from(*days))),
+            Recurrence::Weekly { weekdays } if weekdays.is_empty() => date.checked_add_signed(Duration::days(7)),
+            Recurrence::Weekly { weekdays } => (1..=7)
+                .filter_map(|offset| date.checked_add_signed(Duration::days(offset)))
+                .find(|next| weekdays.contains(&next.weekday())),
+            Recurrence::Monthly { day } => {
+                let this_month = day_in_month(date.year(), date.month(), *day)?;
+                if this_month > date {
+                    return Some(this_month);
+                }
+                let (year, month) = if date.month() == 12 { (date.year() + 1, 1) } else { (date.year(), date.month() + 1) };
+                day_in_month(year, month, *day)
+            },
+        }
+    }
+}
+
+// The given day of a month, or the month's last day if it is shorter
+fn day_in_month(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    (1..=day).rev().find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+}
+
+// A period of work on a task; `end` is unset while its timer is running
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub start: DateTime<Local>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Local>>,
+}
+
+impl TimeEntry {
+    // Time worked, counting a running entry up to `now`
+    pub fn duration(&self, now: DateTime<Local>) -> Duration {
+        (self.end.unwrap_or(now) - self.start).max(Duration::zero())
+    }
+
+    // The time worked on each day, split at local midnight
+    pub fn by_day(&self, now: DateTime<Local>) -> Vec<(NaiveDate, Duration)> {
+        let end = self.end.unwrap_or(now);
+        let mut days = Vec::new();
+        let mut start = self.start;
+        while start < end {
+            let next_midnight = (start.date_naive() + Duration::days(1))
+                .and_hms_opt(0, 0, 0)
+                .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+                .filter(|midnight| *midnight < end)
+                .unwrap_or(end);
+            days.push((start.date_naive(), next_midnight - start));
+            start = next_midnight;
+        }
+        days
+    }
+}
+
+// Task struct to store task information
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    pub id: u32,
+    pub title: String,
+    pub description: String,
+    pub due_date: Option<NaiveDate>,
+    pub status: TaskStatus,
+    // Defaulted so files written before priorities and tags existed still load
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<Recurrence>,
+    // The task this one is a subtask of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<u32>,
+    // Tasks that must be completed before this one can be
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<u32>,
+    // Unknown for tasks created before timestamps were recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Local>>,
+    // Time tracked with `start` and `stop`, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time_entries: Vec<TimeEntry>,
+    // Reminders about the task are held back until then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<DateTime<Local>>,
+}
+
+impl Task {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    // How long the task took from creation to completion, when both times are known
+    pub fn time_to_complete(&self) -> Option<Duration> {
+        Some(self.completed_at? - self.created_at?)
+    }
+
+    // When the task's timer was started, if it is running
+    pub fn running_since(&self) -> Option<DateTime<Local>> {
+        self.time_entries.last().filter(|entry| entry.end.is_none()).map(|entry| entry.start)
+    }
+
+    // Total time tracked, counting a running timer up to `now`
+    pub fn time_spent(&self, now: DateTime<Local>) -> Duration {
+        self.time_entries.iter().map(|entry| entry.duration(now)).sum()
+    }
+
+    // Stop the running timer, returning the length of the entry it ends
+    pub fn stop_timer(&mut self, now: DateTime<Local>) -> Option<Duration> {
+        let entry = self.time_entries.last_mut().filter(|entry| entry.end.is_none())?;
+        entry.end = Some(now.max(entry.start));
+        Some(entry.duration(now))
+    }
+
+    // A task is overdue when it is still pending after its due date
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.status == TaskStatus::Pending
+            && self.due_date.is_some_and(|date| date < today)
+    }
+}
+
+// Parse a creation or completion time as written to task files
+pub fn parse_timestamp(time: &str) -> Result<DateTime<Local>, String> {
+    DateTime::parse_from_rfc3339(time)
+        .map(|time| time.with_timezone(&Local))
+        .map_err(|_| format!("Invalid timestamp '{}'", time))
+}
+
+// `#1, #2` for messages and task details
+pub fn format_ids(ids: &[u32]) -> String {
+    ids.iter().map(|id| format!("#{}", id)).collect::<Vec<_>>().join(", ")
+}
+
+// Fields supplied when creating a task; the ID and status are assigned by the TaskManager
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NewTask {
+    pub title: String,
+    pub description: String,
+    pub due_date: Option<NaiveDate>,
+    pub priority: Priority,
+    pub tags: Vec<String>,
+    pub recurrence: Option<Recurrence>,
+    pub parent: Option<u32>,
+    pub depends_on: Vec<u32>,
+}
+
+// Field-level changes for `TaskManager::update_task`; `None` leaves a field unchanged
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskUpdate {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    // `Some(None)` clears the due date
+    pub due_date: Option<Option<NaiveDate>>,
+    pub status: Option<TaskStatus>,
+    pub priority: Option<Priority>,
+    pub tags: Option<Vec<String>>,
+    // `Some(None)` stops the task repeating
+    pub recurrence: Option<Option<Recurrence>>,
+    // `Some(None)` makes the task a top-level task again
+    pub parent: Option<Option<u32>>,
+    pub depends_on: Option<Vec<u32>>,
+}
+
+impl TaskUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.description.is_none()
+            && self.due_date.is_none() && self.status.is_none()
+            && self.priority.is_none() && self.tags.is_none()
+            && self.recurrence.is_none() && self.parent.is_none()
+            && self.depends_on.is_none()
+    }
+}
+
+// Parse a comma-separated tag list; tags cannot contain spaces, commas or '|'
+pub fn parse_tags(text: &str) -> Result<Vec<String>, String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+        if tag.contains(|c: char| c.is_whitespace() || c == '|') {
+            return Err(format!("Invalid tag '{}': tags cannot contain spaces or '|'", tag));
+        }
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    Ok(tags)
+}
+
+// Format a duration in its two largest units, e.g. `2d 4h` or `35m`
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        (_, 0) => format!("{}d", days),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_manager::testing;
+    use proptest::prelude::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, DATE_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn due_dates_can_be_relative_to_today() {
+        let today = date("2026-10-19");
+        assert_eq!(parse_due_date("today", today), Ok(today));
+        assert_eq!(parse_due_date("Tomorrow", today), Ok(date("2026-10-20")));
+        assert_eq!(parse_due_date("+3d", today), Ok(date("2026-10-22")));
+        assert_eq!(parse_due_date("+2w", today), Ok(date("2026-11-02")));
+        assert_eq!(parse_due_date("2027-01-31", today), Ok(date("2027-01-31")));
+        assert!(parse_due_date("+3m", today).is_err());
+        assert!(parse_due_date("2026-02-30", today).is_err());
+    }
+
+    #[test]
+    fn bad_relative_due_dates_are_errors() {
+        let today = date("2026-10-19");
+        assert_eq!(parse_due_date("+3é", today), Err(TaskError::InvalidRelativeDate("+3é".to_string())));
+        assert_eq!(parse_due_date("+é", today), Err(TaskError::InvalidRelativeDate("+é".to_string())));
+        assert_eq!(
+            parse_due_date("+99999999999999d", today),
+            Err(TaskError::DateOutOfRange("+99999999999999d".to_string())),
+        );
+        assert_eq!(
+            parse_due_date("+2000000000000000000w", today),
+            Err(TaskError::DateOutOfRange("+2000000000000000000w".to_string())),
+        );
+    }
+
+    #[test]
+    fn spans_take_a_unit() {
+        assert_eq!(parse_span("30m"), Ok(Duration::minutes(30)));
+        assert_eq!(parse_span("2H"), Ok(Duration::hours(2)));
+        assert_eq!(parse_span("1w"), Ok(Duration::weeks(1)));
+        assert!(parse_span("30").is_err());
+        assert!(parse_span("-1d").is_err());
+    }
+
+    #[test]
+    fn bad_spans_are_errors() {
+        assert_eq!(parse_span("3é"), Err(TaskError::InvalidSpan("3é".to_string())));
+        assert_eq!(parse_span("é"), Err(TaskError::InvalidSpan("é".to_string())));
+        assert_eq!(parse_span("d"), Err(TaskError::InvalidSpan("d".to_string())));
+        assert_eq!(
+            parse_span("9000000000000000000w"),
+            Err(TaskError::SpanOutOfRange("9000000000000000000w".to_string())),
+        );
+    }
+
+    #[test]
+    fn tags_are_deduplicated_ignoring_case() {
+        assert_eq!(parse_tags("work, home,,Work"), Ok(vec!["work".to_string(), "home".to_string()]));
+        assert!(parse_tags("two words").is_err());
+        assert!(parse_tags("a|b").is_err());
+    }
+
+    #[test]
+    fn recurrences_are_normalized_when_parsed() {
+        assert_eq!(
+            Recurrence::parse("weekly:thu,mon,thu"),
+            Ok(Recurrence::Weekly { weekdays: vec![Weekday::Mon, Weekday::Thu] })
+        );
+        assert_eq!(Recurrence::parse("every:3"), Ok(Recurrence::EveryNDays { days: 3 }));
+        assert!(Recurrence::parse("monthly:32").is_err());
+        assert!(Recurrence::parse("every:0d").is_err());
+        assert!(Recurrence::parse("weekly:").is_err());
+    }
+
+    #[test]
+    fn monthly_recurrences_fall_on_the_last_day_of_short_months() {
+        let monthly = Recurrence::Monthly { day: 31 };
+        assert_eq!(monthly.next_after(date("2026-01-31")), Some(date("2026-02-28")));
+        assert_eq!(monthly.next_after(date("2026-02-28")), Some(date("2026-03-31")));
+        assert_eq!(monthly.next_after(date("2026-12-31")), Some(date("2027-01-31")));
+    }
+
+    #[test]
+    fn durations_show_their_two_largest_units() {
+        assert_eq!(format_duration(Duration::minutes(35)), "35m");
+        assert_eq!(format_duration(Duration::minutes(125)), "2h 5m");
+        assert_eq!(format_duration(Duration::days(3)), "3d");
+        assert_eq!(format_duration(Duration::hours(52)), "2d 4h");
+    }
+
+    proptest! {
+        #[test]
+        fn recurrences_parse_back_from_their_display(recurrence in testing::recurrence()) {
+            prop_assert_eq!(Recurrence::parse(&recurrence.to_string()), Ok(recurrence));
+        }
+
+        #[test]
+        fn recurrences_always_move_forward(recurrence in testing::recurrence(), date in testing::date()) {
+            let next = recurrence.next_after(date).unwrap();
+            prop_assert!(next > date);
+            prop_assert!(next - date <= Duration::days(1000));
+        }
+    }
+}
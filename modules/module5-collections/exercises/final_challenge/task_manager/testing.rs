@@ -0,0 +1,151 @@
+// Helpers shared by the unit and property tests of the task manager
+
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::{env, fs, process};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Weekday};
+use proptest::prelude::*;
+
+use super::reminders::Clock;
+use super::task::{parse_timestamp, Priority, Recurrence, Task, TaskStatus, TimeEntry, DATE_FORMAT};
+
+pub fn task(id: u32, title: &str) -> Task {
+    Task {
+        id,
+        title: title.to_string(),
+        description: format!("Description of {}", title),
+        due_date: None,
+        status: TaskStatus::Pending,
+        priority: Priority::default(),
+        tags: Vec::new(),
+        recurrence: None,
+        parent: None,
+        depends_on: Vec::new(),
+        created_at: None,
+        completed_at: None,
+        time_entries: Vec::new(),
+        snoozed_until: None,
+    }
+}
+
+pub fn due_task(id: u32, title: &str, due: &str) -> Task {
+    Task { due_date: Some(NaiveDate::parse_from_str(due, DATE_FORMAT).unwrap()), ..task(id, title) }
+}
+
+// A clock that only moves when told to
+pub struct FixedClock(Cell<DateTime<Local>>);
+
+impl FixedClock {
+    pub fn at(time: &str) -> FixedClock {
+        FixedClock(Cell::new(parse_timestamp(time).unwrap()))
+    }
+
+    pub fn advance(&self, span: Duration) {
+        self.0.set(self.0.get() + span);
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Local> {
+        self.0.get()
+    }
+}
+
+// A directory of its own for a test's files, removed along with them when dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> TempDir {
+        let path = env::temp_dir().join(format!("task-manager-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn file(&self, name: &str) -> String {
+        self.0.join(name).to_string_lossy().into_owned()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+// Text that every storage format can hold: the legacy format has no way to write '|' or line breaks
+pub fn storable_text() -> impl Strategy<Value = String> {
+    "[^|\r\n]{0,24}"
+}
+
+// A tag list as `parse_tags` would produce it: no separators and no case-insensitive duplicates
+pub fn tags() -> impl Strategy<Value = Vec<String>> {
+    prop::collection::btree_set("[a-z0-9_-]{1,8}", 0..4).prop_map(|tags| tags.into_iter().collect())
+}
+
+pub fn date() -> impl Strategy<Value = NaiveDate> {
+    (0i64..20_000).prop_map(|days| NaiveDate::from_ymd_opt(2000, 1, 1).unwrap() + Duration::days(days))
+}
+
+// Whole seconds, as the stored timestamps are compared exactly
+pub fn time() -> impl Strategy<Value = DateTime<Local>> {
+    (946_684_800i64..2_000_000_000).prop_map(|seconds| Local.timestamp_opt(seconds, 0).unwrap())
+}
+
+// Recurrences in the form `Recurrence::parse` produces, with weekdays sorted and unique
+pub fn recurrence() -> impl Strategy<Value = Recurrence> {
+    prop_oneof![
+        Just(Recurrence::Daily),
+        prop::collection::btree_set(0u8..7, 0..7).prop_map(|days| Recurrence::Weekly {
+            weekdays: days.into_iter().map(|day| Weekday::try_from(day).unwrap()).collect(),
+        }),
+        (1u32..=31).prop_map(|day| Recurrence::Monthly { day }),
+        (1u32..1000).prop_map(|days| Recurrence::EveryNDays { days }),
+    ]
+}
+
+pub fn priority() -> impl Strategy<Value = Priority> {
+    prop_oneof![Just(Priority::Low), Just(Priority::Medium), Just(Priority::High), Just(Priority::Urgent)]
+}
+
+pub fn status() -> impl Strategy<Value = TaskStatus> {
+    prop_oneof![Just(TaskStatus::Pending), Just(TaskStatus::Completed)]
+}
+
+fn time_entry() -> impl Strategy<Value = TimeEntry> {
+    (time(), prop::option::of(0i64..100_000))
+        .prop_map(|(start, length)| TimeEntry { start, end: length.map(|seconds| start + Duration::seconds(seconds)) })
+}
+
+// Lists of tasks numbered from 1, with every field filled in at random
+pub fn tasks() -> impl Strategy<Value = Vec<Task>> {
+    let task = (
+        (storable_text(), storable_text(), prop::option::of(date()), status(), priority(), tags()),
+        (prop::option::of(recurrence()), prop::option::of(1u32..20), prop::collection::vec(1u32..20, 0..3)),
+        (prop::option::of(time()), prop::option::of(time()), prop::collection::vec(time_entry(), 0..3), prop::option::of(time())),
+    );
+    prop::collection::vec(task, 0..6).prop_map(|tasks| {
+        tasks.into_iter()
+            .zip(1..)
+            .map(|(((title, description, due_date, status, priority, tags), (recurrence, parent, depends_on), times), id)| {
+                let (created_at, completed_at, time_entries, snoozed_until) = times;
+                Task {
+                    id,
+                    title,
+                    description,
+                    due_date,
+                    status,
+                    priority,
+                    tags,
+                    recurrence,
+                    parent,
+                    depends_on,
+                    created_at,
+                    completed_at,
+                    time_entries,
+                    snoozed_until,
+                }
+            })
+            .collect()
+    })
+}
@@ -0,0 +1,141 @@
+//! Typed client for the `hello_icp` canister.
+//!
+//! The client encodes arguments and decodes replies using the canister's Candid
+//! interface (`src/hello_icp/hello_icp.did`). Calls go through a [`Transport`],
+//! so the same client works against a replica (`AgentTransport`, behind the
+//! `agent` feature) or against the in-memory [`MockTransport`] in tests.
+
+mod transport;
+
+pub use transport::{MockTransport, Transport, TransportError};
+
+#[cfg(feature = "agent")]
+pub use transport::AgentTransport;
+
+use candid::{CandidType, Decode, Deserialize, Encode};
+use std::fmt;
+
+/// Error returned by the canister itself, mirroring `GreetingError` in the Candid interface.
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum GreetingError {
+    Unauthorized,
+    InvalidArgument(String),
+    EntryNotFound(u64),
+    ReactionNotFound,
+    LowCycles { balance: u128, threshold: u128 },
+}
+
+/// Error returned by [`HelloIcpClient`] methods.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The call did not reach the canister or was rejected by it.
+    Transport(TransportError),
+    /// The argument could not be encoded or the reply did not match the interface.
+    Candid(candid::Error),
+    /// The canister processed the call and returned an error.
+    Canister(GreetingError),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::Transport(e) => write!(f, "{}", e),
+            ClientError::Candid(e) => write!(f, "candid error: {}", e),
+            ClientError::Canister(e) => write!(f, "canister error: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<TransportError> for ClientError {
+    fn from(error: TransportError) -> Self {
+        ClientError::Transport(error)
+    }
+}
+
+impl From<candid::Error> for ClientError {
+    fn from(error: candid::Error) -> Self {
+        ClientError::Candid(error)
+    }
+}
+
+/// Client wrapping the public methods of the `hello_icp` canister.
+pub struct HelloIcpClient<T: Transport> {
+    transport: T,
+}
+
+impl<T: Transport> HelloIcpClient<T> {
+    pub fn new(transport: T) -> HelloIcpClient<T> {
+        HelloIcpClient { transport }
+    }
+
+    // Access the underlying transport, e.g. to configure a mock
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub async fn get_greeting(&self) -> Result<String, ClientError> {
+        let reply = self.transport.query("get_greeting", Encode!()?).await?;
+        Ok(Decode!(&reply, String)?)
+    }
+
+    pub async fn update_greeting(&self, new_greeting: &str) -> Result<String, ClientError> {
+        let arg = Encode!(&new_greeting)?;
+        let reply = self.transport.update("update_greeting", arg).await?;
+        Decode!(&reply, Result<String, GreetingError>)?.map_err(ClientError::Canister)
+    }
+
+    pub async fn get_greeting_history(&self) -> Result<Vec<String>, ClientError> {
+        let reply = self.transport.query("get_greeting_history", Encode!()?).await?;
+        Ok(Decode!(&reply, Vec<String>)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn update_is_visible_to_queries() {
+        let client = HelloIcpClient::new(MockTransport::new());
+
+        block_on(async {
+            assert_eq!(client.get_greeting().await.unwrap(), "Hello, World!");
+            assert_eq!(client.update_greeting("Hi ICP").await.unwrap(), "Hi ICP");
+            assert_eq!(client.get_greeting().await.unwrap(), "Hi ICP");
+            assert_eq!(
+                client.get_greeting_history().await.unwrap(),
+                vec!["Hello, World!".to_string(), "Hi ICP".to_string()]
+            );
+        });
+    }
+
+    #[test]
+    fn transport_errors_are_surfaced() {
+        let client = HelloIcpClient::new(MockTransport::new());
+        client
+            .transport()
+            .fail_next(TransportError::Unavailable("replica down".to_string()));
+
+        let error = block_on(client.get_greeting()).unwrap_err();
+        assert!(matches!(error, ClientError::Transport(TransportError::Unavailable(_))));
+        // The failure only applies to a single call
+        assert!(block_on(client.get_greeting()).is_ok());
+    }
+
+    #[test]
+    fn canister_errors_are_typed() {
+        let client = HelloIcpClient::new(MockTransport::new());
+        client.transport().set_low_cycles(true);
+
+        let error = block_on(client.update_greeting("Hi ICP")).unwrap_err();
+        assert!(matches!(
+            error,
+            ClientError::Canister(GreetingError::LowCycles { .. })
+        ));
+        // Queries keep working while updates are refused
+        assert_eq!(block_on(client.get_greeting()).unwrap(), "Hello, World!");
+    }
+}
@@ -0,0 +1,171 @@
+use crate::GreetingError;
+use async_trait::async_trait;
+use candid::{Decode, Encode};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Error raised while delivering a call to the canister.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransportError {
+    /// The canister (or the replica) rejected the call.
+    Rejected { code: u32, message: String },
+    /// The call could not be delivered at all.
+    Unavailable(String),
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransportError::Rejected { code, message } => {
+                write!(f, "call rejected (code {}): {}", code, message)
+            }
+            TransportError::Unavailable(reason) => write!(f, "canister unavailable: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+/// Moves Candid-encoded arguments and replies between the client and a canister.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn query(&self, method: &str, arg: Vec<u8>) -> Result<Vec<u8>, TransportError>;
+    async fn update(&self, method: &str, arg: Vec<u8>) -> Result<Vec<u8>, TransportError>;
+}
+
+// Reject code used by the replica when the canister traps or the method is unknown
+const CANISTER_ERROR: u32 = 5;
+
+// Cycle threshold of a freshly installed canister
+const DEFAULT_CYCLE_THRESHOLD: u128 = 100_000_000_000;
+
+/// In-memory stand-in for the hello_icp canister, for tests that run without a replica.
+pub struct MockTransport {
+    history: Mutex<Vec<String>>,
+    next_error: Mutex<Option<TransportError>>,
+    low_cycles: AtomicBool,
+}
+
+impl MockTransport {
+    // Create a mock in the same state as a freshly installed canister
+    pub fn new() -> MockTransport {
+        MockTransport {
+            history: Mutex::new(vec!["Hello, World!".to_string()]),
+            next_error: Mutex::new(None),
+            low_cycles: AtomicBool::new(false),
+        }
+    }
+
+    // Make the next call fail with the given error instead of reaching the mock canister
+    pub fn fail_next(&self, error: TransportError) {
+        *self.next_error.lock().unwrap() = Some(error);
+    }
+
+    // Simulate a canister whose cycle balance is below its threshold
+    pub fn set_low_cycles(&self, low_cycles: bool) {
+        self.low_cycles.store(low_cycles, Ordering::SeqCst);
+    }
+
+    fn dispatch(&self, method: &str, arg: &[u8]) -> Result<Vec<u8>, TransportError> {
+        if let Some(error) = self.next_error.lock().unwrap().take() {
+            return Err(error);
+        }
+
+        let mut history = self.history.lock().unwrap();
+        let reply = match method {
+            "get_greeting" => Encode!(history.last().unwrap()),
+            "get_greeting_history" => Encode!(&*history),
+            "update_greeting" => {
+                let new_greeting = Decode!(arg, String).map_err(reject)?;
+                let result = if self.low_cycles.load(Ordering::SeqCst) {
+                    Err(GreetingError::LowCycles {
+                        balance: 0,
+                        threshold: DEFAULT_CYCLE_THRESHOLD,
+                    })
+                } else {
+                    history.push(new_greeting.clone());
+                    Ok(new_greeting)
+                };
+                Encode!(&result)
+            }
+            _ => {
+                return Err(TransportError::Rejected {
+                    code: CANISTER_ERROR,
+                    message: format!("Canister has no method '{}'", method),
+                })
+            }
+        };
+        reply.map_err(reject)
+    }
+}
+
+impl Default for MockTransport {
+    fn default() -> Self {
+        MockTransport::new()
+    }
+}
+
+fn reject(error: candid::Error) -> TransportError {
+    TransportError::Rejected {
+        code: CANISTER_ERROR,
+        message: error.to_string(),
+    }
+}
+
+#[async_trait]
+impl Transport for MockTransport {
+    async fn query(&self, method: &str, arg: Vec<u8>) -> Result<Vec<u8>, TransportError> {
+        self.dispatch(method, &arg)
+    }
+
+    async fn update(&self, method: &str, arg: Vec<u8>) -> Result<Vec<u8>, TransportError> {
+        self.dispatch(method, &arg)
+    }
+}
+
+/// Transport backed by an `ic_agent::Agent` talking to a real replica.
+#[cfg(feature = "agent")]
+pub struct AgentTransport {
+    agent: ic_agent::Agent,
+    canister_id: candid::Principal,
+}
+
+#[cfg(feature = "agent")]
+impl AgentTransport {
+    pub fn new(agent: ic_agent::Agent, canister_id: candid::Principal) -> AgentTransport {
+        AgentTransport { agent, canister_id }
+    }
+}
+
+#[cfg(feature = "agent")]
+fn agent_error(error: ic_agent::AgentError) -> TransportError {
+    match error {
+        ic_agent::AgentError::ReplicaError { reject_code, reject_message } => {
+            TransportError::Rejected { code: reject_code as u32, message: reject_message }
+        }
+        other => TransportError::Unavailable(other.to_string()),
+    }
+}
+
+#[cfg(feature = "agent")]
+#[async_trait]
+impl Transport for AgentTransport {
+    async fn query(&self, method: &str, arg: Vec<u8>) -> Result<Vec<u8>, TransportError> {
+        self.agent
+            .query(&self.canister_id, method)
+            .with_arg(arg)
+            .call()
+            .await
+            .map_err(agent_error)
+    }
+
+    async fn update(&self, method: &str, arg: Vec<u8>) -> Result<Vec<u8>, TransportError> {
+        self.agent
+            .update(&self.canister_id, method)
+            .with_arg(arg)
+            .call_and_wait()
+            .await
+            .map_err(agent_error)
+    }
+}
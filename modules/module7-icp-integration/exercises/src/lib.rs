@@ -1,50 +1,399 @@
 use ic_cdk::export::{
-    candid::{CandidType, Deserialize},
+    candid::{CandidType, Decode, Deserialize, Encode},
+    Principal,
 };
 use ic_cdk_macros::*;
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+use ic_stable_structures::{
+    BoundedStorable, DefaultMemoryImpl, StableBTreeMap, StableCell, StableLog, Storable,
+};
+use std::borrow::Cow;
 use std::cell::RefCell;
+use std::cmp::Reverse;
+
+type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+// Each stable structure gets its own virtual memory so they can grow independently
+const HISTORY_INDEX_MEMORY_ID: MemoryId = MemoryId::new(0);
+const HISTORY_DATA_MEMORY_ID: MemoryId = MemoryId::new(1);
+const CONFIG_MEMORY_ID: MemoryId = MemoryId::new(2);
+const AUDIT_INDEX_MEMORY_ID: MemoryId = MemoryId::new(3);
+const AUDIT_DATA_MEMORY_ID: MemoryId = MemoryId::new(4);
+const REACTIONS_MEMORY_ID: MemoryId = MemoryId::new(5);
+const REACTION_TOTALS_MEMORY_ID: MemoryId = MemoryId::new(6);
+const REACTION_RANKING_MEMORY_ID: MemoryId = MemoryId::new(7);
+
+const DEFAULT_GREETING: &str = "Hello, World!";
+
+// Upper bound on the number of items returned by a single query
+const MAX_PAGE_SIZE: u64 = 100;
+
+// Longest accepted reaction, in bytes (enough for emoji ZWJ sequences)
+const MAX_EMOJI_BYTES: u32 = 32;
+
+// Cycle balance below which heavy update calls are refused, unless configured otherwise
+const DEFAULT_CYCLE_THRESHOLD: u128 = 100_000_000_000;
+
+// Errors returned to callers of the canister
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+enum GreetingError {
+    Unauthorized,
+    InvalidArgument(String),
+    EntryNotFound(u64),
+    ReactionNotFound,
+    LowCycles { balance: u128, threshold: u128 },
+}
 
-// Define a struct to hold the greeting message
+// Canister settings that can be changed by administrators
 #[derive(CandidType, Deserialize, Clone, Default)]
-struct GreetingState {
-    message: String,
-    history: Vec<String>,
+struct Config {
+    admins: Vec<Principal>,
+    // Optional so configs saved before the threshold existed still decode
+    cycle_threshold: Option<u128>,
+}
+
+// Cycle balance of the canister and whether heavy operations are currently refused
+#[derive(CandidType, Deserialize, Clone, Debug)]
+struct CyclesStatus {
+    balance: u128,
+    threshold: u128,
+    low_cycles: bool,
+}
+
+// Kind of privileged operation recorded in the audit log
+#[derive(CandidType, Deserialize, Clone, Debug)]
+enum AuditAction {
+    AdminAdded,
+    AdminRemoved,
+    ConfigUpdated,
+}
+
+// A single entry of the append-only audit log
+#[derive(CandidType, Deserialize, Clone, Debug)]
+struct AuditEntry {
+    action: AuditAction,
+    caller: Principal,
+    timestamp: u64,
+    parameters: Vec<(String, String)>,
+}
+
+// One page of the audit log, along with the total number of entries
+#[derive(CandidType, Deserialize)]
+struct AuditPage {
+    entries: Vec<AuditEntry>,
+    total: u64,
+}
+
+// Number of reactions with a given emoji on a history entry
+#[derive(CandidType, Deserialize, Clone, Debug)]
+struct ReactionCount {
+    emoji: String,
+    count: u64,
+}
+
+// A greeting history entry together with its aggregated reactions
+#[derive(CandidType, Deserialize, Clone, Debug)]
+struct HistoryEntry {
+    id: u64,
+    greeting: String,
+    reactions: Vec<ReactionCount>,
+    total_reactions: u64,
+}
+
+// One page of the greeting history, along with the total number of entries
+#[derive(CandidType, Deserialize)]
+struct HistoryPage {
+    entries: Vec<HistoryEntry>,
+    total: u64,
+}
+
+// Key of a single reaction: each principal has at most one reaction per entry
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct ReactionKey {
+    entry_id: u64,
+    reactor: Principal,
+}
+
+impl ReactionKey {
+    // Smallest key for an entry, used to scan all of its reactions
+    fn first_of(entry_id: u64) -> ReactionKey {
+        ReactionKey {
+            entry_id,
+            reactor: Principal::from_slice(&[]),
+        }
+    }
+}
+
+// Emoji stored for a reaction, bounded so it can be a stable map value
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Emoji(String);
+
+// Fixed-layout encoding: big-endian entry id, principal length, principal bytes
+impl Storable for ReactionKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let reactor = self.reactor.as_slice();
+        let mut bytes = Vec::with_capacity(9 + reactor.len());
+        bytes.extend_from_slice(&self.entry_id.to_be_bytes());
+        bytes.push(reactor.len() as u8);
+        bytes.extend_from_slice(reactor);
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let mut entry_id = [0; 8];
+        entry_id.copy_from_slice(&bytes[..8]);
+        let len = bytes[8] as usize;
+        ReactionKey {
+            entry_id: u64::from_be_bytes(entry_id),
+            reactor: Principal::from_slice(&bytes[9..9 + len]),
+        }
+    }
+}
+
+impl BoundedStorable for ReactionKey {
+    const MAX_SIZE: u32 = 8 + 1 + 29;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+impl Storable for Emoji {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.0.as_bytes())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Emoji(String::from_utf8(bytes.into_owned()).expect("invalid emoji in stable memory"))
+    }
 }
 
-// Create a thread-local variable to store the state
+impl BoundedStorable for Emoji {
+    const MAX_SIZE: u32 = MAX_EMOJI_BYTES;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Candid-encoded types stored in stable memory
+impl Storable for Config {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode config"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("failed to decode config")
+    }
+}
+
+impl Storable for AuditEntry {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode audit entry"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("failed to decode audit entry")
+    }
+}
+
+// Create thread-local variables to store the state.
+// Everything lives directly in stable memory, so it survives upgrades
+// without being serialized and is not limited by the size of the heap.
 thread_local! {
-    static STATE: RefCell<GreetingState> = RefCell::new(GreetingState {
-        message: "Hello, World!".to_string(),
-        history: vec!["Hello, World!".to_string()],
-    });
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
+        RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+
+    static HISTORY: RefCell<StableLog<String, Memory, Memory>> = RefCell::new(
+        MEMORY_MANAGER.with(|manager| {
+            let manager = manager.borrow();
+            StableLog::init(
+                manager.get(HISTORY_INDEX_MEMORY_ID),
+                manager.get(HISTORY_DATA_MEMORY_ID),
+            )
+            .expect("failed to initialize the greeting history")
+        })
+    );
+
+    static CONFIG: RefCell<StableCell<Config, Memory>> = RefCell::new(
+        MEMORY_MANAGER.with(|manager| {
+            StableCell::init(manager.borrow().get(CONFIG_MEMORY_ID), Config::default())
+                .expect("failed to initialize the config")
+        })
+    );
+
+    static AUDIT_LOG: RefCell<StableLog<AuditEntry, Memory, Memory>> = RefCell::new(
+        MEMORY_MANAGER.with(|manager| {
+            let manager = manager.borrow();
+            StableLog::init(
+                manager.get(AUDIT_INDEX_MEMORY_ID),
+                manager.get(AUDIT_DATA_MEMORY_ID),
+            )
+            .expect("failed to initialize the audit log")
+        })
+    );
+
+    static REACTIONS: RefCell<StableBTreeMap<ReactionKey, Emoji, Memory>> = RefCell::new(
+        MEMORY_MANAGER.with(|manager| {
+            StableBTreeMap::init(manager.borrow().get(REACTIONS_MEMORY_ID))
+        })
+    );
+
+    // Total number of reactions per history entry, kept in sync with REACTIONS
+    // so ranking entries does not require scanning every reaction
+    static REACTION_TOTALS: RefCell<StableBTreeMap<u64, u64, Memory>> = RefCell::new(
+        MEMORY_MANAGER.with(|manager| {
+            StableBTreeMap::init(manager.borrow().get(REACTION_TOTALS_MEMORY_ID))
+        })
+    );
+
+    // The same totals keyed by (total, entry id), most reacted first and older entries
+    // first on ties, so the top entries are read without sorting every total
+    static REACTION_RANKING: RefCell<StableBTreeMap<(Reverse<u64>, u64), (), Memory>> = RefCell::new(
+        MEMORY_MANAGER.with(|manager| {
+            StableBTreeMap::init(manager.borrow().get(REACTION_RANKING_MEMORY_ID))
+        })
+    );
 }
 
 #[init]
 fn init() {
     // Initialize the canister state with a default greeting
-    STATE.with(|state| {
-        let mut state = state.borrow_mut();
-        state.message = "Hello, World!".to_string();
-        state.history = vec!["Hello, World!".to_string()];
+    HISTORY.with(|history| {
+        let history = history.borrow();
+        if history.is_empty() {
+            history
+                .append(&DEFAULT_GREETING.to_string())
+                .expect("failed to append to the greeting history");
+        }
     });
+
+    // Whoever installs the canister becomes its first administrator
+    update_config(|config| config.admins = vec![ic_cdk::caller()]);
+}
+
+#[post_upgrade]
+fn post_upgrade() {
+    // Nothing to restore: the state is read straight from stable memory.
+    // Touch it once so a corrupted log traps the upgrade instead of a later call.
+    HISTORY.with(|history| history.borrow().len());
+    AUDIT_LOG.with(|log| log.borrow().len());
+
+    // Canisters installed before administrators existed get the upgrading controller
+    if get_admins().is_empty() {
+        let caller = ic_cdk::caller();
+        update_config(|config| config.admins = vec![caller]);
+        record_audit(
+            AuditAction::AdminAdded,
+            vec![
+                ("principal".to_string(), caller.to_text()),
+                ("reason".to_string(), "no administrators on upgrade".to_string()),
+            ],
+        );
+    }
+
+    // Canisters upgraded from before the ranking existed build it from the totals
+    if REACTION_RANKING.with(|ranking| ranking.borrow().is_empty()) {
+        REACTION_TOTALS.with(|totals| {
+            REACTION_RANKING.with(|ranking| {
+                let mut ranking = ranking.borrow_mut();
+                for (entry_id, total) in totals.borrow().iter() {
+                    ranking.insert((Reverse(total), entry_id), ());
+                }
+            })
+        });
+    }
+}
+
+fn is_admin(principal: &Principal) -> bool {
+    CONFIG.with(|config| config.borrow().get().admins.contains(principal))
+}
+
+// Return an error unless the caller is an administrator
+fn require_admin() -> Result<(), GreetingError> {
+    if is_admin(&ic_cdk::caller()) {
+        Ok(())
+    } else {
+        Err(GreetingError::Unauthorized)
+    }
+}
+
+fn update_config(f: impl FnOnce(&mut Config)) {
+    CONFIG.with(|config| {
+        let mut config = config.borrow_mut();
+        let mut updated = config.get().clone();
+        f(&mut updated);
+        config.set(updated).expect("failed to save the config");
+    });
+}
+
+// Append a privileged operation performed by the caller to the audit log
+fn record_audit(action: AuditAction, parameters: Vec<(String, String)>) {
+    let entry = AuditEntry {
+        action,
+        caller: ic_cdk::caller(),
+        timestamp: ic_cdk::api::time(),
+        parameters,
+    };
+    AUDIT_LOG.with(|log| {
+        log.borrow()
+            .append(&entry)
+            .expect("failed to append to the audit log");
+    });
+}
+
+fn cycle_threshold() -> u128 {
+    CONFIG.with(|config| {
+        config
+            .borrow()
+            .get()
+            .cycle_threshold
+            .unwrap_or(DEFAULT_CYCLE_THRESHOLD)
+    })
+}
+
+fn cycles_status() -> CyclesStatus {
+    let balance = ic_cdk::api::canister_balance128();
+    let threshold = cycle_threshold();
+    CyclesStatus {
+        balance,
+        threshold,
+        low_cycles: balance < threshold,
+    }
+}
+
+// Refuse heavy operations while the cycle balance is below the threshold,
+// so the canister degrades to read-only instead of running out of cycles
+fn require_cycles() -> Result<(), GreetingError> {
+    let status = cycles_status();
+    if status.low_cycles {
+        Err(GreetingError::LowCycles {
+            balance: status.balance,
+            threshold: status.threshold,
+        })
+    } else {
+        Ok(())
+    }
 }
 
 #[update]
-fn update_greeting(new_greeting: String) -> String {
+fn update_greeting(new_greeting: String) -> Result<String, GreetingError> {
+    require_cycles()?;
+
     // Update the greeting and return the new greeting
-    STATE.with(|state| {
-        let mut state = state.borrow_mut();
-        state.message = new_greeting.clone();
-        state.history.push(new_greeting.clone());
-        new_greeting
-    })
+    HISTORY.with(|history| {
+        history
+            .borrow()
+            .append(&new_greeting)
+            .expect("failed to append to the greeting history");
+    });
+    Ok(new_greeting)
 }
 
 #[query]
 fn get_greeting() -> String {
-    // Return the current greeting
-    STATE.with(|state| {
-        state.borrow().message.clone()
+    // The current greeting is always the latest history entry
+    HISTORY.with(|history| {
+        let history = history.borrow();
+        history
+            .len()
+            .checked_sub(1)
+            .and_then(|last| history.get(last))
+            .unwrap_or_else(|| DEFAULT_GREETING.to_string())
     })
 }
 
@@ -52,7 +401,316 @@ fn get_greeting() -> String {
 #[query]
 fn get_greeting_history() -> Vec<String> {
     // Return the greeting history
-    STATE.with(|state| {
-        state.borrow().history.clone()
+    HISTORY.with(|history| history.borrow().iter().collect())
+}
+
+#[query]
+fn get_admins() -> Vec<Principal> {
+    CONFIG.with(|config| config.borrow().get().admins.clone())
+}
+
+#[update]
+fn add_admin(principal: Principal) -> Result<(), GreetingError> {
+    require_admin()?;
+    if is_admin(&principal) {
+        return Ok(());
+    }
+
+    update_config(|config| config.admins.push(principal));
+    record_audit(
+        AuditAction::AdminAdded,
+        vec![("principal".to_string(), principal.to_text())],
+    );
+    Ok(())
+}
+
+#[update]
+fn remove_admin(principal: Principal) -> Result<(), GreetingError> {
+    require_admin()?;
+    if !is_admin(&principal) {
+        return Ok(());
+    }
+    if get_admins().len() == 1 {
+        return Err(GreetingError::InvalidArgument(
+            "cannot remove the last administrator".to_string(),
+        ));
+    }
+
+    update_config(|config| config.admins.retain(|admin| *admin != principal));
+    record_audit(
+        AuditAction::AdminRemoved,
+        vec![("principal".to_string(), principal.to_text())],
+    );
+    Ok(())
+}
+
+// Read the audit log, oldest entry first, `limit` entries starting at `offset`
+#[query]
+fn get_audit_log(offset: u64, limit: u64) -> Result<AuditPage, GreetingError> {
+    require_admin()?;
+    AUDIT_LOG.with(|log| {
+        let log = log.borrow();
+        let total = log.len();
+        let end = offset
+            .saturating_add(limit.min(MAX_PAGE_SIZE))
+            .min(total);
+        let entries = (offset..end).filter_map(|index| log.get(index)).collect();
+        Ok(AuditPage { entries, total })
+    })
+}
+
+// Whether a character is an emoji pictograph, including the regional indicators of
+// flags and the skin tone modifiers
+fn is_pictograph(c: char) -> bool {
+    matches!(
+        u32::from(c),
+        0x00A9 | 0x00AE | 0x203C | 0x2049 | 0x2122 | 0x2139
+            | 0x2194..=0x2199 | 0x21A9..=0x21AA | 0x231A..=0x231B | 0x2328 | 0x23CF
+            | 0x23E9..=0x23F3 | 0x23F8..=0x23FA | 0x24C2 | 0x25AA..=0x25AB | 0x25B6 | 0x25C0
+            | 0x25FB..=0x25FE | 0x2600..=0x27BF | 0x2934..=0x2935 | 0x2B05..=0x2B07
+            | 0x2B1B..=0x2B1C | 0x2B50 | 0x2B55 | 0x3030 | 0x303D | 0x3297 | 0x3299
+            | 0x1F000..=0x1FAFF
+    )
+}
+
+// Whether a character only joins or modifies the emoji around it: the zero width joiner,
+// the keycap mark, variation selectors and the tags of subdivision flags
+fn is_emoji_component(c: char) -> bool {
+    matches!(u32::from(c), 0x200D | 0x20E3 | 0xFE0E..=0xFE0F | 0xE0020..=0xE007F)
+}
+
+// Check that a reaction is a short, non-empty emoji sequence
+fn validate_emoji(emoji: &str) -> Result<(), GreetingError> {
+    if emoji.is_empty() || emoji.len() > MAX_EMOJI_BYTES as usize {
+        return Err(GreetingError::InvalidArgument(format!(
+            "reaction must be between 1 and {} bytes",
+            MAX_EMOJI_BYTES
+        )));
+    }
+    let not_an_emoji = || GreetingError::InvalidArgument("reaction must be an emoji".to_string());
+    let mut has_pictograph = false;
+    for (index, c) in emoji.char_indices() {
+        // Keycap sequences such as "1️⃣" start with a digit, '#' or '*'
+        let rest = &emoji[index + c.len_utf8()..];
+        let is_keycap = matches!(c, '0'..='9' | '#' | '*')
+            && (rest.starts_with('\u{20E3}') || rest.starts_with("\u{FE0F}\u{20E3}"));
+        if is_pictograph(c) || is_keycap {
+            has_pictograph = true;
+        } else if !is_emoji_component(c) {
+            return Err(not_an_emoji());
+        }
+    }
+    if !has_pictograph {
+        return Err(not_an_emoji());
+    }
+    Ok(())
+}
+
+// Return an error unless the caller is authenticated
+fn require_authenticated() -> Result<Principal, GreetingError> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        Err(GreetingError::Unauthorized)
+    } else {
+        Ok(caller)
+    }
+}
+
+// Build the history entry with the given id, including its reaction counts
+fn history_entry(entry_id: u64) -> Option<HistoryEntry> {
+    let greeting = HISTORY.with(|history| history.borrow().get(entry_id))?;
+
+    let mut reactions: Vec<ReactionCount> = Vec::new();
+    REACTIONS.with(|map| {
+        let map = map.borrow();
+        let range = ReactionKey::first_of(entry_id)..ReactionKey::first_of(entry_id + 1);
+        for (_, Emoji(emoji)) in map.range(range) {
+            match reactions.iter_mut().find(|r| r.emoji == emoji) {
+                Some(reaction) => reaction.count += 1,
+                None => reactions.push(ReactionCount { emoji, count: 1 }),
+            }
+        }
+    });
+    reactions.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.emoji.cmp(&b.emoji)));
+    let total_reactions = reactions.iter().map(|r| r.count).sum();
+
+    Some(HistoryEntry {
+        id: entry_id,
+        greeting,
+        reactions,
+        total_reactions,
     })
-} 
\ No newline at end of file
+}
+
+// Update an entry's reaction total and its place in the ranking
+fn adjust_reaction_total(entry_id: u64, added: bool) {
+    REACTION_TOTALS.with(|totals| {
+        REACTION_RANKING.with(|ranking| {
+            let mut totals = totals.borrow_mut();
+            let mut ranking = ranking.borrow_mut();
+            let current = totals.get(&entry_id).unwrap_or(0);
+            let updated = if added { current + 1 } else { current.saturating_sub(1) };
+            ranking.remove(&(Reverse(current), entry_id));
+            if updated == 0 {
+                totals.remove(&entry_id);
+            } else {
+                totals.insert(entry_id, updated);
+                ranking.insert((Reverse(updated), entry_id), ());
+            }
+        })
+    });
+}
+
+// React to a history entry; a new reaction replaces the caller's previous one
+#[update]
+fn react(entry_id: u64, emoji: String) -> Result<HistoryEntry, GreetingError> {
+    let caller = require_authenticated()?;
+    require_cycles()?;
+    validate_emoji(&emoji)?;
+    if HISTORY.with(|history| entry_id >= history.borrow().len()) {
+        return Err(GreetingError::EntryNotFound(entry_id));
+    }
+
+    let key = ReactionKey {
+        entry_id,
+        reactor: caller,
+    };
+    let previous = REACTIONS.with(|map| map.borrow_mut().insert(key, Emoji(emoji)));
+    if previous.is_none() {
+        adjust_reaction_total(entry_id, true);
+    }
+
+    history_entry(entry_id).ok_or(GreetingError::EntryNotFound(entry_id))
+}
+
+// Remove the caller's reaction from a history entry
+#[update]
+fn remove_reaction(entry_id: u64) -> Result<HistoryEntry, GreetingError> {
+    let caller = require_authenticated()?;
+    require_cycles()?;
+    if HISTORY.with(|history| entry_id >= history.borrow().len()) {
+        return Err(GreetingError::EntryNotFound(entry_id));
+    }
+
+    let key = ReactionKey {
+        entry_id,
+        reactor: caller,
+    };
+    REACTIONS
+        .with(|map| map.borrow_mut().remove(&key))
+        .ok_or(GreetingError::ReactionNotFound)?;
+    adjust_reaction_total(entry_id, false);
+
+    history_entry(entry_id).ok_or(GreetingError::EntryNotFound(entry_id))
+}
+
+// Return `limit` greeting history entries starting at `offset`, with the reactions on each
+#[query]
+fn get_greeting_history_entries(offset: u64, limit: u64) -> HistoryPage {
+    let total = HISTORY.with(|history| history.borrow().len());
+    let end = offset
+        .saturating_add(limit.min(MAX_PAGE_SIZE))
+        .min(total);
+    let entries = (offset..end).filter_map(history_entry).collect();
+    HistoryPage { entries, total }
+}
+
+// Return up to `limit` history entries, most reacted first.
+// Ties go to the older entry, and entries without reactions come last.
+#[query]
+fn top_greetings(limit: u64) -> Vec<HistoryEntry> {
+    let limit = limit.min(MAX_PAGE_SIZE) as usize;
+    let mut ids: Vec<u64> = REACTION_RANKING.with(|ranking| {
+        ranking
+            .borrow()
+            .iter()
+            .map(|((_, entry_id), ())| entry_id)
+            .take(limit)
+            .collect()
+    });
+    if ids.len() < limit {
+        let len = HISTORY.with(|history| history.borrow().len());
+        let unreacted = (0..len)
+            .filter(|id| REACTION_TOTALS.with(|totals| !totals.borrow().contains_key(id)))
+            .take(limit - ids.len())
+            .collect::<Vec<_>>();
+        ids.extend(unreacted);
+    }
+
+    ids.into_iter().filter_map(history_entry).collect()
+}
+
+#[query]
+fn get_cycles_status() -> CyclesStatus {
+    cycles_status()
+}
+
+// Accept all cycles attached to the call and return the updated status
+#[update]
+fn deposit_cycles() -> CyclesStatus {
+    let available = ic_cdk::api::call::msg_cycles_available128();
+    ic_cdk::api::call::msg_cycles_accept128(available);
+    cycles_status()
+}
+
+#[update]
+fn set_cycle_threshold(threshold: u128) -> Result<CyclesStatus, GreetingError> {
+    require_admin()?;
+
+    let previous = cycle_threshold();
+    update_config(|config| config.cycle_threshold = Some(threshold));
+    record_audit(
+        AuditAction::ConfigUpdated,
+        vec![
+            ("setting".to_string(), "cycle_threshold".to_string()),
+            ("previous".to_string(), previous.to_string()),
+            ("value".to_string(), threshold.to_string()),
+        ],
+    );
+    Ok(cycles_status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emoji_reactions_are_accepted() {
+        for emoji in ["🎉", "👍🏽", "1️⃣", "#️⃣", "👩‍💻", "❤️", "🇫🇷", "🏴󠁧󠁢󠁳󠁣󠁴󠁿"] {
+            assert_eq!(validate_emoji(emoji), Ok(()), "{}", emoji);
+        }
+    }
+
+    #[test]
+    fn text_reactions_are_refused() {
+        for emoji in ["", "a", "+1", ":)", "🎉 🎉", "🎉🎉🎉🎉🎉🎉🎉🎉🎉", "1", "a\u{20E3}", "\u{200D}"] {
+            assert!(validate_emoji(emoji).is_err(), "{}", emoji);
+        }
+    }
+
+    #[test]
+    fn accented_and_cjk_text_is_refused() {
+        for emoji in ["é", "ñoño", "Ünïcödé", "日本", "漢字🎉", "한국어", "🎉é"] {
+            assert!(validate_emoji(emoji).is_err(), "{}", emoji);
+        }
+    }
+
+    #[test]
+    fn the_ranking_follows_reaction_totals() {
+        let ranked = || {
+            REACTION_RANKING.with(|ranking| {
+                ranking.borrow().iter().map(|((_, entry_id), ())| entry_id).collect::<Vec<_>>()
+            })
+        };
+        for entry_id in [3, 3, 1, 2] {
+            adjust_reaction_total(entry_id, true);
+        }
+        assert_eq!(ranked(), [3, 1, 2]);
+        adjust_reaction_total(3, false);
+        assert_eq!(ranked(), [1, 2, 3]);
+        adjust_reaction_total(2, true);
+        adjust_reaction_total(1, false);
+        assert_eq!(ranked(), [2, 3]);
+        assert_eq!(REACTION_TOTALS.with(|totals| totals.borrow().get(&1)), None);
+    }
+}